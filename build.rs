@@ -0,0 +1,71 @@
+//! With the `builtin-templates` feature, collects the templates in `templates/` into one file
+//! in the API's `list?format=json` format, for `src/builtin.rs` to build into the program.
+
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+const TEMPLATES_DIR: &str = "templates";
+const SNAPSHOT_FILE: &str = "list.json";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", TEMPLATES_DIR);
+    if std::env::var_os("CARGO_FEATURE_BUILTIN_TEMPLATES").is_none() {
+        return;
+    }
+    let dir = Path::new(TEMPLATES_DIR);
+    let mut templates = read_snapshot(&dir.join(SNAPSHOT_FILE));
+    // A <Name>.gitignore file replaces the template with the same name in the snapshot
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .unwrap_or_else(|error| panic!("Can't read {}: {}", dir.display(), error))
+        .map(|entry| entry.expect("Can't read the templates directory").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "gitignore")
+        })
+        .collect();
+    paths.sort();
+    for path in paths {
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let text = std::fs::read_to_string(&path)
+            .unwrap_or_else(|error| panic!("Can't read {}: {}", path.display(), error));
+        let key = name.to_lowercase();
+        templates.insert(
+            key.clone(),
+            json!({
+                "key": key,
+                "name": name,
+                "fileName": format!("{}.gitignore", name),
+                "contents": format!("\n### {} ###\n{}\n", name, text.trim_end()),
+            }),
+        );
+    }
+    if templates.is_empty() {
+        panic!(
+            "The builtin-templates feature builds in the templates in {0}/, but there aren't any. \
+             Save the API's snapshot in {0}/{1} with\n  \
+             {0}/update-snapshot.sh\n\
+             or add <Name>.gitignore files to {0}/",
+            TEMPLATES_DIR, SNAPSHOT_FILE
+        );
+    }
+    let out_dir = std::env::var_os("OUT_DIR").expect("Cargo sets OUT_DIR");
+    let json = serde_json::to_string(&templates).expect("The templates can always be serialized");
+    std::fs::write(Path::new(&out_dir).join("templates.json"), json)
+        .expect("Can't write the built-in templates");
+}
+
+/// Reads the templates in a snapshot of the API's `list?format=json`, if there is one.
+fn read_snapshot(path: &Path) -> Map<String, Value> {
+    let json = match std::fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Map::new(),
+        Err(error) => panic!("Can't read {}: {}", path.display(), error),
+    };
+    match serde_json::from_str(&json) {
+        Ok(Value::Object(templates)) => templates,
+        Ok(_) => panic!("{} isn't a JSON object of templates", path.display()),
+        Err(error) => panic!("{} isn't valid JSON: {}", path.display(), error),
+    }
+}
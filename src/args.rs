@@ -1,14 +1,131 @@
+use gig_gen::Format;
+
 #[derive(Debug, clap::Args)]
 pub(super) struct FilterArgs {
     /// Filter to apply to list of templates
     pub(super) filter: Option<String>,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub(super) format: OutputFormat,
+    /// Where to get the templates from
+    #[arg(long, value_enum, default_value_t = Source::Api)]
+    pub(super) source: Source,
 }
 
 #[derive(Debug, clap::Args)]
 pub(super) struct TemplateArgs {
-    /// One or more gitignore templates
-    #[arg(name = "template", required = true)]
+    /// One or more gitignore templates. Defaults to the templates in the configuration file
+    #[arg(name = "template")]
     pub(super) templates: Vec<String>,
+    /// Add the templates in a profile from the configuration file
+    #[arg(short, long)]
+    pub(super) profile: Option<String>,
+    /// Write the file to this path instead of to stdout, or to stdout if the path is -
+    #[arg(short, long)]
+    pub(super) output: Option<std::path::PathBuf>,
+    /// Kind of ignore file to generate
+    #[arg(long, value_enum, default_value_t = FileFormat::Gitignore)]
+    pub(super) format: FileFormat,
+    /// Output format. Anything but plain lists the rules from each template
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub(super) output_format: OutputFormat,
+    /// Fail if the templates have changed since the lockfile was written.
+    /// Defaults to the templates in the lockfile
+    #[arg(long)]
+    pub(super) locked: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct PathArgs {
+    /// Path of a file or directory, relative to the current directory
+    pub(super) path: String,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct LintArgs {
+    /// The file to check
+    #[arg(default_value = ".gitignore")]
+    pub(super) file: std::path::PathBuf,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub(super) format: OutputFormat,
+    /// Fix the problems that can be fixed without changing which files are ignored
+    #[arg(long)]
+    pub(super) fix: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct OutdatedArgs {
+    /// Only check these templates
+    #[arg(name = "template")]
+    pub(super) templates: Vec<String>,
+    /// The .gitignore file to check. Defaults to the configured output path or .gitignore
+    #[arg(short, long)]
+    pub(super) file: Option<std::path::PathBuf>,
+    /// Show how each changed template's rules have changed
+    #[arg(long)]
+    pub(super) diff: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct UpgradeArgs {
+    /// The .gitignore file to upgrade. Defaults to the configured output path or .gitignore
+    #[arg(short, long)]
+    pub(super) file: Option<std::path::PathBuf>,
+    /// Don't ask for confirmation before changing the file
+    #[arg(short, long)]
+    pub(super) yes: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub(super) struct ServeArgs {
+    /// The address and port to listen on
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    pub(super) bind: String,
+    /// A directory of <Name>.gitignore files to serve along with the public templates.
+    /// They replace public templates with the same name
+    #[arg(short, long)]
+    pub(super) templates: Option<std::path::PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub(super) enum Source {
+    /// The template API, or the cache if it can't be reached
+    Api,
+    /// The templates built into the program
+    Builtin,
+}
+
+/// The values of `--format` for each kind of ignore file.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub(super) enum FileFormat {
+    Gitignore,
+    Dockerignore,
+    Npmignore,
+    Prettierignore,
+    Eslintignore,
+    Hgignore,
+}
+
+impl From<FileFormat> for Format {
+    fn from(format: FileFormat) -> Self {
+        match format {
+            FileFormat::Gitignore => Format::Gitignore,
+            FileFormat::Dockerignore => Format::Dockerignore,
+            FileFormat::Npmignore => Format::Npmignore,
+            FileFormat::Prettierignore => Format::Prettierignore,
+            FileFormat::Eslintignore => Format::Eslintignore,
+            FileFormat::Hgignore => Format::Hgignore,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub(super) enum OutputFormat {
+    Plain,
+    Json,
+    Yaml,
+    Csv,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -19,6 +136,18 @@ pub(super) enum Commands {
     Generate(TemplateArgs),
     /// Pick templates interactively and generate a .gitignore file (default).
     Interactive,
+    /// Show which rule in the `.gitignore` file ignores a path.
+    Explain(PathArgs),
+    /// List untracked files that aren't ignored, and templates that would ignore them.
+    Audit,
+    /// Check a `.gitignore` file for duplicate, shadowed, dead and invalid rules.
+    Lint(LintArgs),
+    /// List the templates that have changed since the `.gitignore` file was generated.
+    Outdated(OutdatedArgs),
+    /// Update the generated part of the `.gitignore` file to the current templates.
+    Upgrade(UpgradeArgs),
+    /// Serve the templates with the same API as gitignore.io.
+    Serve(ServeArgs),
 }
 
 #[derive(Debug, clap::Parser)]
@@ -27,4 +156,7 @@ pub(super) struct Args {
     /// Optional subcommand
     #[clap(subcommand)]
     pub(super) command: Option<Commands>,
+    /// Check the cached templates with the API, however recently they were fetched
+    #[arg(long, global = true)]
+    pub(super) refresh: bool,
 }
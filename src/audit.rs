@@ -0,0 +1,39 @@
+use crate::gitignore::Gitignore;
+use crate::gitignore_api::TemplateInfo;
+use crate::matcher::Matcher;
+
+/// A template that would ignore some of the untracked paths.
+pub struct Suggestion {
+    pub template: String,
+    pub paths: Vec<String>,
+}
+
+/// Returns the templates that would ignore any of `paths`, with the ones that
+/// would ignore the most first.
+pub fn suggest_templates(paths: &[String], templates: &[TemplateInfo]) -> Vec<Suggestion> {
+    let mut suggestions: Vec<Suggestion> = templates
+        .iter()
+        .filter_map(|template| {
+            let gitignore = Gitignore::parse(&template.contents);
+            let matcher = Matcher::new(&gitignore);
+            let paths: Vec<String> = paths
+                .iter()
+                .filter(|path| match path.strip_suffix('/') {
+                    Some(dir) => matcher.is_ignored(dir, true),
+                    None => matcher.is_ignored(path, false),
+                })
+                .cloned()
+                .collect();
+            if paths.is_empty() {
+                None
+            } else {
+                Some(Suggestion {
+                    template: template.key.clone(),
+                    paths,
+                })
+            }
+        })
+        .collect();
+    suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.paths.len()));
+    suggestions
+}
@@ -0,0 +1,94 @@
+use crate::gitignore::{Gitignore, Line};
+use crate::gitignore_api;
+use crate::normalize;
+
+/// The lines of a `.gitignore` file that were generated from templates, from the
+/// `# Created by .../api/<names>` line to the matching `# End of .../api/<names>` line.
+#[derive(Clone, Debug)]
+pub struct GeneratedBlock {
+    /// The indexes of the block's first and last lines
+    start: usize,
+    end: usize,
+    templates: Vec<String>,
+}
+
+impl GeneratedBlock {
+    /// The names of the templates the block was generated from, in lowercase.
+    pub fn templates(&self) -> &[String] {
+        &self.templates
+    }
+    pub fn contains(&self, template: &str) -> bool {
+        self.templates
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(template))
+    }
+    pub fn lines<'a>(&self, gitignore: &'a Gitignore) -> &'a [Line] {
+        &gitignore.lines()[self.start..=self.end]
+    }
+}
+
+/// Returns the generated blocks in `gitignore`, in order. A block without an
+/// `# End of` line runs to the end of the file.
+pub fn find_blocks(gitignore: &Gitignore) -> Vec<GeneratedBlock> {
+    let lines = gitignore.lines();
+    let mut blocks = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let text = lines[index].text().trim_end();
+        let Some(url) = text.strip_prefix("# Created by ") else {
+            index += 1;
+            continue;
+        };
+        let templates = gitignore_api::parse_template_names(text);
+        if templates.is_empty() {
+            index += 1;
+            continue;
+        }
+        let end_line = format!("# End of {}", url);
+        let end = lines[index..]
+            .iter()
+            .position(|line| line.text().trim_end() == end_line)
+            .map(|offset| index + offset)
+            .unwrap_or(lines.len() - 1);
+        blocks.push(GeneratedBlock {
+            start: index,
+            end,
+            templates,
+        });
+        index = end + 1;
+    }
+    blocks
+}
+/// Returns what `block` would contain if it were generated from `template_text`, the
+/// current contents of its templates. Rules that are already in effect before the block
+/// are left out, the same way they are when templates are appended to a file.
+pub fn regenerate(gitignore: &Gitignore, block: &GeneratedBlock, template_text: &str) -> String {
+    let before = join(&gitignore.lines()[..block.start]);
+    normalize::dedupe(&before, template_text.trim_end_matches('\n'))
+}
+/// Returns the text of `gitignore` with each of `blocks` replaced by the matching `contents`.
+/// A block is removed if its contents are empty.
+pub fn replace(gitignore: &Gitignore, blocks: &[GeneratedBlock], contents: &[String]) -> String {
+    let lines = gitignore.lines();
+    let mut texts: Vec<&str> = Vec::new();
+    let mut index = 0;
+    for (block, contents) in blocks.iter().zip(contents) {
+        texts.extend(lines[index..block.start].iter().map(|line| line.text()));
+        if !contents.is_empty() {
+            texts.extend(contents.trim_end_matches('\n').split('\n'));
+        }
+        index = block.end + 1;
+    }
+    texts.extend(lines[index..].iter().map(|line| line.text()));
+    let mut text = texts.join("\n");
+    if gitignore.to_string().ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+fn join(lines: &[Line]) -> String {
+    lines
+        .iter()
+        .map(|line| format!("{}\n", line.text()))
+        .collect()
+}
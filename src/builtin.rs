@@ -0,0 +1,32 @@
+use crate::error::{Error, Result};
+use crate::gitignore_api::{self, TemplateInfo};
+
+/// The snapshot of the templates built into the program, in the API's `list?format=json` format.
+/// `build.rs` writes it from the templates in `templates/`.
+#[cfg(feature = "builtin-templates")]
+const SNAPSHOT: Option<&str> = Some(include_str!(concat!(env!("OUT_DIR"), "/templates.json")));
+#[cfg(not(feature = "builtin-templates"))]
+const SNAPSHOT: Option<&str> = None;
+
+/// The source of the built-in templates.
+pub(crate) const SOURCE: &str = "builtin";
+
+/// Returns true if the program was built with the `builtin-templates` feature.
+pub(crate) fn is_available() -> bool {
+    SNAPSHOT.is_some()
+}
+/// Returns the templates built into the program, ordered by name.
+pub(crate) fn templates() -> Result<Vec<TemplateInfo>> {
+    let Some(json) = SNAPSHOT else {
+        return Err(Error::Invalid(
+            "gig-gen was built without the builtin-templates feature".to_string(),
+        ));
+    };
+    gitignore_api::parse_templates(json, SOURCE, false)
+        .map_err(|error| Error::parse("the built-in templates", error))
+}
+/// Returns a `.gitignore` file generated from the named built-in templates, the same
+/// way the API generates it from `url`.
+pub(crate) fn generate(url: &str, template_names: &[String]) -> Result<String> {
+    gitignore_api::generate(url, &templates()?, template_names)
+}
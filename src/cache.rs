@@ -0,0 +1,89 @@
+use crate::config;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// How long a cached file is used before it is fetched again, unless the configuration says otherwise.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The headers that tell whether a cached file has changed since it was fetched.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Validators {
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<String>,
+}
+
+/// Returns how long a cached file is used before it is fetched again. If `refresh` is true,
+/// cached files are checked with the API however new they are.
+pub(crate) fn max_age(refresh: bool) -> Duration {
+    if refresh {
+        return Duration::ZERO;
+    }
+    config::get()
+        .cache
+        .max_age
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_MAX_AGE)
+}
+
+/// Returns the contents of a cached file, if it was written less than `max_age` ago.
+pub(crate) fn read(name: &str, max_age: Duration) -> Option<String> {
+    let path = path(name)?;
+    let modified = std::fs::metadata(&path).ok()?.modified().ok()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    if age > max_age {
+        return None;
+    }
+    std::fs::read_to_string(path).ok()
+}
+/// Writes a file to the cache. The cache is only an optimization, so errors are ignored.
+pub(crate) fn write(name: &str, contents: &str) {
+    if let Some(dir) = dir() {
+        let _ =
+            std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(dir.join(name), contents));
+    }
+}
+/// Returns the validators stored with a cached file, or none if there aren't any.
+pub(crate) fn read_validators(name: &str) -> Validators {
+    read(&validators_name(name), Duration::MAX)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+pub(crate) fn write_validators(name: &str, validators: &Validators) {
+    if let Ok(json) = serde_json::to_string(validators) {
+        write(&validators_name(name), &json);
+    }
+}
+fn validators_name(name: &str) -> String {
+    format!("{}.validators.json", name)
+}
+/// Returns the path of a cached file, or `None` if the cache is turned off.
+pub(crate) fn path(name: &str) -> Option<PathBuf> {
+    Some(dir()?.join(name))
+}
+/// Returns the configured cache directory, or `$XDG_CACHE_HOME/gig-gen`, `$HOME/.cache/gig-gen`
+/// or `%LOCALAPPDATA%\gig-gen`. Returns `None` if the cache is turned off.
+fn dir() -> Option<PathBuf> {
+    let config = &config::get().cache;
+    if config.enabled == Some(false) {
+        return None;
+    }
+    if let Some(dir) = &config.dir {
+        return Some(dir.clone());
+    }
+    user_dir()
+}
+/// Returns `$XDG_CACHE_HOME/gig-gen`, `$HOME/.cache/gig-gen` or `%LOCALAPPDATA%\gig-gen`,
+/// whether the cache is turned on or not.
+pub(crate) fn user_dir() -> Option<PathBuf> {
+    let env_dir = |name| {
+        std::env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+    };
+    env_dir("XDG_CACHE_HOME")
+        .or_else(|| env_dir("HOME").map(|home| home.join(".cache")))
+        .or_else(|| env_dir("LOCALAPPDATA"))
+        .map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+}
@@ -0,0 +1,81 @@
+use crate::config;
+use crate::error::Result;
+use crate::gitignore_api::{self, TemplateInfo};
+use crate::http::{self, ProxyEnvironment};
+use crate::normalize;
+
+/// Gets templates from the cache, the template API or the built-in templates. Each client
+/// has its own settings for how current the templates have to be and which proxies the
+/// requests go through, and the other settings come from the configuration files.
+///
+/// ```no_run
+/// let client = gig_gen::Client::new()?.refresh_cache(true);
+/// let rules = client.generate(&["rust".to_string(), "vim".to_string()])?;
+/// # Ok::<(), gig_gen::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Client {
+    pub(crate) refresh: bool,
+    pub(crate) require_current: bool,
+    pub(crate) proxy_environment: ProxyEnvironment,
+}
+
+impl Client {
+    /// Creates a client that sends requests through the proxies in the environment variables,
+    /// and trusts the certificate authorities in the configured CA bundle as well as the
+    /// system's. Fails if the CA bundle can't be read.
+    ///
+    /// minreq reads the proxy variables and the certificates it trusts from the environment
+    /// itself, so the first client takes the proxy variables out of the process's environment,
+    /// and sets `SSL_CERT_FILE` if there's a CA bundle. Create it before starting any threads.
+    pub fn new() -> Result<Self> {
+        let proxy_environment = http::take_proxy_environment();
+        if let Some(ca_bundle) = &config::get().network.ca_bundle {
+            http::trust_ca_bundle(ca_bundle)?;
+        }
+        Ok(Client {
+            refresh: false,
+            require_current: false,
+            proxy_environment,
+        })
+    }
+    /// If `refresh` is true, the cached templates are checked with the API before they're used,
+    /// however recently they were fetched. Only the templates that have changed are downloaded.
+    pub fn refresh_cache(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+    /// If `require` is true, the templates always come from the API: the cached templates are
+    /// checked with it before they're used, and if that fails, the error is returned instead of
+    /// using an older cached copy or the built-in templates.
+    pub fn require_current_templates(mut self, require: bool) -> Self {
+        self.require_current = require;
+        self
+    }
+    /// Sends requests through the proxies in `environment` instead of the ones that were in
+    /// the environment variables.
+    pub fn proxy_environment(mut self, environment: ProxyEnvironment) -> Self {
+        self.proxy_environment = environment;
+        self
+    }
+    /// Returns the names of the available templates.
+    pub fn list_templates(&self) -> Result<Vec<String>> {
+        gitignore_api::get_template_names(self)
+    }
+    /// Returns all of the available templates, including their contents, ordered by name.
+    ///
+    /// The templates are cached, and an older cached copy is used if they can't be fetched,
+    /// unless [`Client::require_current_templates`] says otherwise.
+    pub fn templates(&self) -> Result<Vec<TemplateInfo>> {
+        gitignore_api::get_templates(self)
+    }
+    /// Fetches a `.gitignore` file generated from the named templates.
+    pub fn fetch(&self, template_names: &[String]) -> Result<String> {
+        gitignore_api::get_template(self, template_names)
+    }
+    /// Fetches a `.gitignore` file generated from the named templates, leaving out the rules
+    /// that repeat a rule from an earlier template.
+    pub fn generate(&self, template_names: &[String]) -> Result<String> {
+        Ok(normalize::dedupe("", &self.fetch(template_names)?))
+    }
+}
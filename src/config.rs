@@ -0,0 +1,180 @@
+use crate::error::{Error, Result};
+use crate::format::Format;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The name of the configuration file in a repository.
+const REPO_FILE_NAME: &str = ".gig-gen.toml";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Settings read from `config.toml` in the user's configuration directory and from
+/// `.gig-gen.toml` in the repository. A setting in the repository's file replaces
+/// the same setting in the user's file.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The templates to use when none are given
+    pub templates: Option<Vec<String>>,
+    /// The URL of the API the templates come from
+    pub api_url: Option<String>,
+    /// The path the `.gitignore` file is written to. In `.gig-gen.toml`, a relative path is
+    /// relative to the file's directory
+    pub output: Option<PathBuf>,
+    /// How the interactive app and `list` match template names to a filter
+    pub filter_mode: Option<FilterMode>,
+    pub cache: CacheConfig,
+    pub network: NetworkConfig,
+    /// Named sets of templates
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub enabled: Option<bool>,
+    /// How many seconds the cached templates are used before they're fetched again
+    pub max_age: Option<u64>,
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    /// How many seconds a request can take in all, from connecting to reading the end of the
+    /// response. There aren't separate timeouts for connecting and reading
+    pub timeout: Option<u64>,
+    /// How many times a request that failed for a reason that may go away is tried again
+    pub retries: Option<u32>,
+    /// The HTTP proxy to send requests through, as `http://[user[:password]@]host[:port]`
+    pub proxy: Option<String>,
+    /// The hosts and domains to connect to without the proxy
+    pub no_proxy: Option<Vec<String>>,
+    /// A PEM file with the certificates of certificate authorities to trust as well as the
+    /// system's and the ones built into the program
+    pub ca_bundle: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub templates: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMode {
+    /// Names that start with the filter
+    Prefix,
+    /// Names that contain the filter
+    Contains,
+}
+
+impl FilterMode {
+    pub fn matches(&self, name: &str, filter: &str) -> bool {
+        match self {
+            FilterMode::Prefix => name.starts_with(filter),
+            FilterMode::Contains => name.contains(filter),
+        }
+    }
+}
+
+impl Config {
+    /// Returns this configuration with the settings from `other` replacing its own.
+    fn merge(mut self, other: Config) -> Self {
+        self.templates = other.templates.or(self.templates);
+        self.api_url = other.api_url.or(self.api_url);
+        self.output = other.output.or(self.output);
+        self.filter_mode = other.filter_mode.or(self.filter_mode);
+        self.cache.enabled = other.cache.enabled.or(self.cache.enabled);
+        self.cache.max_age = other.cache.max_age.or(self.cache.max_age);
+        self.cache.dir = other.cache.dir.or(self.cache.dir);
+        self.network.timeout = other.network.timeout.or(self.network.timeout);
+        self.network.retries = other.network.retries.or(self.network.retries);
+        self.network.proxy = other.network.proxy.or(self.network.proxy);
+        self.network.no_proxy = other.network.no_proxy.or(self.network.no_proxy);
+        self.network.ca_bundle = other.network.ca_bundle.or(self.network.ca_bundle);
+        self.profiles.extend(other.profiles);
+        self
+    }
+    /// Returns the templates in the profile named `name`, if there is one.
+    pub fn profile(&self, name: &str) -> Option<&[String]> {
+        self.profiles
+            .get(name)
+            .map(|profile| profile.templates.as_slice())
+    }
+}
+
+/// Reads the configuration files. Must be called before `get()` to use them.
+pub fn load() -> Result<()> {
+    let mut config = Config::default();
+    if let Some(path) = user_file() {
+        if let Some(user_config) = read(&path)? {
+            config = config.merge(user_config);
+        }
+    }
+    if let Some(path) = repo_file() {
+        if let Some(mut repo_config) = read(&path)? {
+            // A relative output path is relative to the directory of the `.gig-gen.toml`
+            // file, which may be a parent of the current directory
+            if let (Some(output), Some(dir)) = (&repo_config.output, path.parent()) {
+                repo_config.output = Some(dir.join(output));
+            }
+            config = config.merge(repo_config);
+        }
+    }
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+/// Returns the configuration, or the default configuration if it hasn't been loaded.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+/// Returns the path of the file to write in `format`: the configured output path for a
+/// `.gitignore` file, or the format's usual file name.
+pub fn output_file(format: Format) -> PathBuf {
+    match (format, &get().output) {
+        (Format::Gitignore, Some(output)) => output.clone(),
+        _ => PathBuf::from(format.file_name()),
+    }
+}
+fn read(path: &Path) -> Result<Option<Config>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(Error::io(path, error)),
+    };
+    match toml::from_str(&contents) {
+        Ok(config) => Ok(Some(config)),
+        Err(error) => Err(Error::parse(path.display(), error)),
+    }
+}
+/// Returns `$XDG_CONFIG_HOME/gig-gen/config.toml`, `$HOME/.config/gig-gen/config.toml`
+/// or `%APPDATA%\gig-gen\config.toml`.
+fn user_file() -> Option<PathBuf> {
+    let env_dir = |name| {
+        std::env::var_os(name)
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+    };
+    env_dir("XDG_CONFIG_HOME")
+        .or_else(|| env_dir("HOME").map(|home| home.join(".config")))
+        .or_else(|| env_dir("APPDATA"))
+        .map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("config.toml"))
+}
+/// Returns the `.gig-gen.toml` file in the current directory or the closest parent
+/// directory that has one, without looking outside the repository.
+fn repo_file() -> Option<PathBuf> {
+    let current_dir = std::env::current_dir().ok()?;
+    for dir in current_dir.ancestors() {
+        let path = dir.join(REPO_FILE_NAME);
+        if path.is_file() {
+            return Some(path);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    None
+}
@@ -0,0 +1,109 @@
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Returns a unified diff of the lines in `old` and `new`, or an empty string if they're the same.
+pub fn unified(old: &[&str], new: &[&str], old_name: &str, new_name: &str) -> String {
+    let ops = edit_script(old, new);
+    if ops.iter().all(|op| *op == Op::Equal) {
+        return String::default();
+    }
+    // The position in old and new of each operation
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut old_index, mut new_index) = (0, 0);
+    for op in &ops {
+        positions.push((old_index, new_index));
+        match op {
+            Op::Equal => {
+                old_index += 1;
+                new_index += 1;
+            }
+            Op::Delete => old_index += 1,
+            Op::Insert => new_index += 1,
+        }
+    }
+    let mut text = format!("--- {}\n+++ {}\n", old_name, new_name);
+    let mut start = 0;
+    while let Some(offset) = ops[start..].iter().position(|op| *op != Op::Equal) {
+        // A hunk goes from the context before the first change to the context after the
+        // last change that's close enough to it for their context to overlap
+        let first_change = start + offset;
+        let mut last_change = first_change;
+        let mut index = first_change + 1;
+        while index < ops.len() {
+            if ops[index] != Op::Equal {
+                last_change = index;
+            } else if index - last_change > 2 * CONTEXT {
+                break;
+            }
+            index += 1;
+        }
+        let hunk_start = first_change.saturating_sub(CONTEXT).max(start);
+        let hunk_end = (last_change + CONTEXT + 1).min(ops.len());
+        let hunk = &ops[hunk_start..hunk_end];
+        let (old_start, new_start) = positions[hunk_start];
+        let old_count = hunk.iter().filter(|op| **op != Op::Insert).count();
+        let new_count = hunk.iter().filter(|op| **op != Op::Delete).count();
+        text += format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_count),
+            range(new_start, new_count)
+        )
+        .as_str();
+        for (op, (old_index, new_index)) in hunk.iter().zip(&positions[hunk_start..hunk_end]) {
+            let line = match op {
+                Op::Equal => format!(" {}", old[*old_index]),
+                Op::Delete => format!("-{}", old[*old_index]),
+                Op::Insert => format!("+{}", new[*new_index]),
+            };
+            text += line.as_str();
+            text.push('\n');
+        }
+        start = hunk_end;
+    }
+    text
+}
+/// Formats the start and length of a hunk's lines. An empty range starts at the line before it.
+fn range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+/// Returns the operations that turn `old` into `new`, keeping their longest common subsequence.
+fn edit_script(old: &[&str], new: &[&str]) -> Vec<Op> {
+    // lengths[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut ops = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push(Op::Equal);
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            ops.push(Op::Delete);
+            i += 1;
+        } else {
+            ops.push(Op::Insert);
+            j += 1;
+        }
+    }
+    ops
+}
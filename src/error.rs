@@ -0,0 +1,121 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// An error from one of the library's functions.
+///
+/// Each kind of error has its own process exit status, returned by [`Error::exit_code`]:
+///
+/// | Status | Error |
+/// |--------|-------|
+/// | 1 | Problems found by `lint` or `outdated`, or any other error |
+/// | 2 | [`Error::Invalid`], or invalid command line arguments |
+/// | 3 | [`Error::Network`] |
+/// | 4 | [`Error::HttpStatus`] |
+/// | 5 | [`Error::UnknownTemplate`] |
+/// | 6 | [`Error::Io`] |
+/// | 7 | [`Error::Parse`] |
+/// | 8 | [`Error::Cache`] |
+/// | 9 | [`Error::Locked`] |
+#[derive(Debug)]
+pub enum Error {
+    /// A request to the template API failed.
+    Network(minreq::Error),
+    /// The template API answered a request with an error status.
+    HttpStatus {
+        url: String,
+        status: i32,
+        reason: String,
+    },
+    /// There is no template with the name.
+    UnknownTemplate(String),
+    /// Reading or writing a file failed.
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A configuration file, lockfile or API response isn't in the expected format.
+    Parse {
+        /// The path or URL the text came from
+        input: String,
+        message: String,
+    },
+    /// The cached copy of the templates can't be used.
+    Cache { path: PathBuf, message: String },
+    /// The templates don't match the ones recorded in the lockfile.
+    Locked(String),
+    /// The command can't be carried out with the arguments and configuration it was given.
+    Invalid(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+    pub(crate) fn parse(input: impl fmt::Display, error: impl fmt::Display) -> Self {
+        Error::Parse {
+            input: input.to_string(),
+            message: error.to_string(),
+        }
+    }
+    /// Returns the status the program exits with when it fails with this error.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Invalid(_) => 2,
+            Error::Network(_) => 3,
+            Error::HttpStatus { .. } => 4,
+            Error::UnknownTemplate(_) => 5,
+            Error::Io { .. } => 6,
+            Error::Parse { .. } => 7,
+            Error::Cache { .. } => 8,
+            Error::Locked(_) => 9,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Network(error) => write!(f, "Problem getting templates: {}", error),
+            Error::HttpStatus {
+                url,
+                status,
+                reason,
+            } => write!(f, "Problem getting {}: {} {}", url, status, reason),
+            Error::UnknownTemplate(name) => write!(f, r#"There is no template named "{}""#, name),
+            Error::Io { path, source } => {
+                write!(f, r#"Problem with "{}": {}"#, path.display(), source)
+            }
+            Error::Parse { input, message } => {
+                write!(f, r#"Problem reading "{}": {}"#, input, message)
+            }
+            Error::Cache { path, message } => write!(
+                f,
+                r#"The cached templates in "{}" can't be used, delete the file and try again: {}"#,
+                path.display(),
+                message
+            ),
+            Error::Locked(message) | Error::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Network(error) => Some(error),
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<minreq::Error> for Error {
+    fn from(error: minreq::Error) -> Self {
+        Error::Network(error)
+    }
+}
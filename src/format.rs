@@ -0,0 +1,260 @@
+use crate::gitignore::{Gitignore, Rule};
+
+/// A kind of ignore file that template rules can be written as.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Gitignore,
+    Dockerignore,
+    Npmignore,
+    Prettierignore,
+    Eslintignore,
+    Hgignore,
+}
+
+/// Template rules translated into another format.
+pub struct Conversion {
+    pub text: String,
+    /// Descriptions of the rules that couldn't be translated exactly.
+    pub warnings: Vec<String>,
+}
+
+impl Format {
+    pub const ALL: [Format; 6] = [
+        Format::Gitignore,
+        Format::Dockerignore,
+        Format::Npmignore,
+        Format::Prettierignore,
+        Format::Eslintignore,
+        Format::Hgignore,
+    ];
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Format::Gitignore => ".gitignore",
+            Format::Dockerignore => ".dockerignore",
+            Format::Npmignore => ".npmignore",
+            Format::Prettierignore => ".prettierignore",
+            Format::Eslintignore => ".eslintignore",
+            Format::Hgignore => ".hgignore",
+        }
+    }
+}
+
+/// Translates the rules in `gitignore` into `format`.
+pub fn convert(gitignore: &Gitignore, format: Format) -> Conversion {
+    match format {
+        // npm, Prettier and ESLint match ignore files the same way git does
+        Format::Gitignore | Format::Npmignore | Format::Prettierignore | Format::Eslintignore => {
+            Conversion {
+                text: gitignore.to_string(),
+                warnings: vec![],
+            }
+        }
+        Format::Dockerignore => to_dockerignore(gitignore),
+        Format::Hgignore => to_hgignore(gitignore),
+    }
+}
+
+/// Docker matches each pattern against the whole path from the root of the build context,
+/// using Go's `filepath.Match` syntax plus `**`, and trims whitespace from each line.
+fn to_dockerignore(gitignore: &Gitignore) -> Conversion {
+    let mut lines = Vec::new();
+    let mut warnings = Vec::new();
+    for line in gitignore.lines() {
+        let Some(rule) = line.rule() else {
+            lines.push(line.text().trim_end().to_string());
+            continue;
+        };
+        let mut warn = |message: &str| {
+            warnings.push(format!("line {}: {}: {}", line.number(), rule, message));
+        };
+        if rule.is_directory_only() {
+            warn("Docker can't match only directories, so it matches files with the same name too");
+        }
+        if rule.pattern().starts_with(' ') || rule.pattern().ends_with("\\ ") {
+            warn("Docker removes the spaces at the start and end of patterns");
+        }
+        lines.push(dockerignore_pattern(rule));
+    }
+    let mut text = lines.join("\n");
+    if gitignore.to_string().ends_with('\n') {
+        text.push('\n');
+    }
+    Conversion { text, warnings }
+}
+fn dockerignore_pattern(rule: &Rule) -> String {
+    let mut pattern = String::new();
+    if rule.is_negated() {
+        pattern.push('!');
+    }
+    if !rule.is_anchored() {
+        pattern.push_str("**/");
+    }
+    // Go writes a negated character class as [^...]
+    let mut chars = rule.pattern().chars().peekable();
+    let mut escaped = false;
+    while let Some(c) = chars.next() {
+        pattern.push(c);
+        if c == '[' && !escaped && chars.peek() == Some(&'!') {
+            chars.next();
+            pattern.push('^');
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    pattern
+}
+
+/// Mercurial matches `glob` patterns in any directory, like git does with a pattern that
+/// doesn't contain a `/`, so those are written as globs, and the rest as regular expressions.
+/// Mercurial can't re-include files, or match only directories.
+fn to_hgignore(gitignore: &Gitignore) -> Conversion {
+    let mut lines = Vec::new();
+    let mut warnings = Vec::new();
+    let mut syntax = None;
+    for line in gitignore.lines() {
+        let Some(rule) = line.rule() else {
+            lines.push(line.text().trim_end().to_string());
+            continue;
+        };
+        let mut warn = |message: &str| {
+            warnings.push(format!("line {}: {}: {}", line.number(), rule, message));
+        };
+        if rule.is_negated() {
+            warn("Mercurial can't re-include files, so the rule is left out");
+            lines.push(format!("# {}", rule));
+            continue;
+        }
+        if rule.is_directory_only() {
+            warn("Mercurial can't match only directories, so it matches files with the same name too");
+        }
+        let (pattern_syntax, pattern) = if rule.is_anchored()
+            || rule.pattern().contains("**")
+            || rule.pattern().contains(['{', '}'])
+        {
+            // Match the path from the root, and everything inside it if it's a directory
+            let regex = rule.to_regex();
+            let regex = regex.strip_suffix('$').unwrap_or(&regex);
+            ("regexp", format!("{}(?:/|$)", regex))
+        } else {
+            ("glob", rule.pattern().to_string())
+        };
+        if syntax != Some(pattern_syntax) {
+            lines.push(format!("syntax: {}", pattern_syntax));
+            syntax = Some(pattern_syntax);
+        }
+        lines.push(pattern);
+    }
+    let mut text = lines.join("\n");
+    if gitignore.to_string().ends_with('\n') {
+        text.push('\n');
+    }
+    Conversion { text, warnings }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dockerignore(text: &str) -> Conversion {
+        convert(&Gitignore::parse(text), Format::Dockerignore)
+    }
+    fn hgignore(text: &str) -> Conversion {
+        convert(&Gitignore::parse(text), Format::Hgignore)
+    }
+
+    #[test]
+    fn dockerignore_matches_unanchored_patterns_in_any_directory() {
+        assert_eq!(dockerignore("*.log\nbuild/\n").text, "**/*.log\n**/build\n");
+    }
+
+    #[test]
+    fn dockerignore_keeps_anchored_patterns_at_the_root() {
+        assert_eq!(
+            dockerignore("/target\ndocs/api\n").text,
+            "target\ndocs/api\n"
+        );
+    }
+
+    #[test]
+    fn dockerignore_keeps_negation() {
+        assert_eq!(
+            dockerignore("*.log\n!/important.log\n").text,
+            "**/*.log\n!important.log\n"
+        );
+    }
+
+    #[test]
+    fn dockerignore_writes_negated_classes_the_go_way() {
+        assert_eq!(
+            dockerignore("[!a]*.tmp\n\\[!x]\n").text,
+            "**/[^a]*.tmp\n**/\\[!x]\n"
+        );
+    }
+
+    #[test]
+    fn dockerignore_keeps_comments_and_blank_lines() {
+        let conversion = dockerignore("# Logs\n\n*.log");
+        assert_eq!(conversion.text, "# Logs\n\n**/*.log");
+        assert!(conversion.warnings.is_empty());
+    }
+
+    #[test]
+    fn dockerignore_warns_about_directory_only_rules() {
+        let conversion = dockerignore("*.log\nbuild/\n");
+        assert_eq!(conversion.warnings.len(), 1);
+        assert!(conversion.warnings[0].starts_with("line 2: build/:"));
+    }
+
+    #[test]
+    fn dockerignore_warns_about_spaces_docker_removes() {
+        let conversion = dockerignore("foo\\ \n");
+        assert_eq!(conversion.warnings.len(), 1);
+        assert!(conversion.warnings[0].contains("spaces"));
+    }
+
+    #[test]
+    fn hgignore_writes_unanchored_patterns_as_globs() {
+        assert_eq!(
+            hgignore("*.log\n*.swp\n").text,
+            "syntax: glob\n*.log\n*.swp\n"
+        );
+    }
+
+    #[test]
+    fn hgignore_writes_anchored_patterns_as_regexes_from_the_root() {
+        assert_eq!(
+            hgignore("/target\ndocs/api\n").text,
+            "syntax: regexp\n^target(?:/|$)\n^docs/api(?:/|$)\n"
+        );
+    }
+
+    #[test]
+    fn hgignore_writes_double_stars_as_regexes() {
+        let conversion = hgignore("**/build\n");
+        assert!(conversion.text.starts_with("syntax: regexp\n"));
+        assert!(conversion.text.ends_with("build(?:/|$)\n"));
+    }
+
+    #[test]
+    fn hgignore_switches_syntax_only_when_it_changes() {
+        assert_eq!(
+            hgignore("*.log\n/target\n/dist\n*.tmp\n").text,
+            "syntax: glob\n*.log\nsyntax: regexp\n^target(?:/|$)\n^dist(?:/|$)\nsyntax: glob\n*.tmp\n"
+        );
+    }
+
+    #[test]
+    fn hgignore_leaves_out_negated_rules() {
+        let conversion = hgignore("*.log\n!important.log\n");
+        assert_eq!(conversion.text, "syntax: glob\n*.log\n# !important.log\n");
+        assert_eq!(conversion.warnings.len(), 1);
+        assert!(conversion.warnings[0].starts_with("line 2: !important.log:"));
+    }
+
+    #[test]
+    fn hgignore_warns_about_directory_only_rules() {
+        let conversion = hgignore("build/\n");
+        assert_eq!(conversion.text, "syntax: glob\nbuild\n");
+        assert_eq!(conversion.warnings.len(), 1);
+    }
+}
@@ -0,0 +1,60 @@
+use crate::gitignore::Gitignore;
+use crate::matcher::Matcher;
+use std::path::Path;
+use std::process::Command;
+
+/// Returns true if `dir` is in a git repository's working tree.
+pub fn is_work_tree(dir: &Path) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+/// Returns the paths of the files git tracks in `dir`, relative to `dir`.
+///
+/// Returns an empty list if `dir` isn't in a git repository, or git isn't installed.
+pub fn tracked_files(dir: &Path) -> Vec<String> {
+    git_paths(dir, &["ls-files", "-z"])
+}
+/// Returns the untracked paths in `dir` that git doesn't ignore, relative to `dir`, as
+/// `git status` shows them. The ignore files in subdirectories, `.git/info/exclude` and
+/// `core.excludesFile` are all applied. A directory without any tracked files is returned
+/// as a whole, with a trailing `/`, and empty directories are left out.
+///
+/// Returns an empty list if `dir` isn't in a git repository, or git isn't installed.
+pub fn untracked_paths(dir: &Path) -> Vec<String> {
+    git_paths(
+        dir,
+        &[
+            "ls-files",
+            "--others",
+            "--exclude-standard",
+            "--directory",
+            "--no-empty-directory",
+            "-z",
+        ],
+    )
+}
+/// Returns the paths of the files git tracks in `dir` that `gitignore` would ignore,
+/// if it was the `.gitignore` file in `dir`.
+pub fn ignored_tracked_files(dir: &Path, gitignore: &Gitignore) -> Vec<String> {
+    let matcher = Matcher::new(gitignore);
+    tracked_files(dir)
+        .into_iter()
+        .filter(|path| matcher.is_ignored(path, false))
+        .collect()
+}
+/// Runs a git command in `dir` that lists paths separated by NUL characters.
+fn git_paths(dir: &Path, args: &[&str]) -> Vec<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output();
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(|path| path.to_string())
+            .collect(),
+        _ => vec![],
+    }
+}
@@ -0,0 +1,483 @@
+use std::fmt;
+
+/// The contents of a `.gitignore` file, parsed into lines.
+///
+/// Each line keeps its original text, so a parsed file turns back into
+/// exactly the same text when it is formatted with `to_string()`.
+#[derive(Clone, Debug, Default)]
+pub struct Gitignore {
+    lines: Vec<Line>,
+    trailing_newline: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Line {
+    number: usize,
+    text: String,
+    section: Option<String>,
+    kind: LineKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LineKind {
+    Blank,
+    Comment,
+    Rule(Rule),
+}
+
+/// A pattern line, as described in `man gitignore`.
+///
+/// The pattern is stored without the leading `!` of a negation, the `/` that
+/// anchors it and the `/` that makes it match only directories. Those are
+/// recorded in the flags instead. Backslash escapes are left in the pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pattern: String,
+    is_negated: bool,
+    is_directory_only: bool,
+    is_anchored: bool,
+}
+
+impl Gitignore {
+    pub fn parse(text: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut section = None;
+        let trailing_newline = text.ends_with('\n');
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        if !text.is_empty() || trailing_newline {
+            for (index, text) in text.split('\n').enumerate() {
+                let kind = LineKind::parse(text);
+                if kind == LineKind::Comment {
+                    if let Some(name) = section_name(text) {
+                        section = Some(name);
+                    } else if text.starts_with("# End of ") {
+                        section = None;
+                    }
+                }
+                lines.push(Line {
+                    number: index + 1,
+                    text: text.to_string(),
+                    section: section.clone(),
+                    kind,
+                });
+            }
+        }
+        Self {
+            lines,
+            trailing_newline,
+        }
+    }
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.lines.iter().filter_map(|line| line.rule())
+    }
+    pub fn retain(&mut self, f: impl FnMut(&Line) -> bool) {
+        self.lines.retain(f);
+    }
+}
+
+impl fmt::Display for Gitignore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", line.text)?;
+        }
+        if self.trailing_newline {
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Line {
+    /// The line's 1-based position in the text it was parsed from.
+    pub fn number(&self) -> usize {
+        self.number
+    }
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+    /// The name of the template section (`### Name ###`) the line is in, if any.
+    pub fn section(&self) -> Option<&str> {
+        self.section.as_deref()
+    }
+    pub fn kind(&self) -> &LineKind {
+        &self.kind
+    }
+    pub fn rule(&self) -> Option<&Rule> {
+        match &self.kind {
+            LineKind::Rule(rule) => Some(rule),
+            _ => None,
+        }
+    }
+}
+
+impl LineKind {
+    fn parse(text: &str) -> Self {
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        if text.starts_with('#') {
+            LineKind::Comment
+        } else {
+            match Rule::parse(text) {
+                Some(rule) => LineKind::Rule(rule),
+                None => LineKind::Blank,
+            }
+        }
+    }
+}
+
+impl Rule {
+    fn parse(text: &str) -> Option<Self> {
+        let mut pattern = trim_trailing_spaces(text);
+        let is_negated = pattern.starts_with('!');
+        if is_negated {
+            pattern = &pattern[1..];
+        }
+        let is_directory_only = pattern.len() > 1 && pattern.ends_with('/');
+        if is_directory_only {
+            pattern = &pattern[..pattern.len() - 1];
+        }
+        let is_anchored = pattern.contains('/');
+        if let Some(unanchored) = pattern.strip_prefix('/') {
+            pattern = unanchored;
+        }
+        if pattern.is_empty() {
+            None
+        } else {
+            Some(Self {
+                pattern: pattern.to_string(),
+                is_negated,
+                is_directory_only,
+                is_anchored,
+            })
+        }
+    }
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+    pub fn is_negated(&self) -> bool {
+        self.is_negated
+    }
+    /// True if the rule had a trailing `/`, so it only matches directories.
+    pub fn is_directory_only(&self) -> bool {
+        self.is_directory_only
+    }
+    /// True if the rule had a `/` at the beginning or in the middle, so it only
+    /// matches relative to the directory of the `.gitignore` file.
+    pub fn is_anchored(&self) -> bool {
+        self.is_anchored
+    }
+    /// Returns a description of what's wrong with the pattern, if git can't use it as intended.
+    pub fn error(&self) -> Option<&'static str> {
+        let chars: Vec<char> = self.pattern.chars().collect();
+        let mut index = 0;
+        while index < chars.len() {
+            match chars[index] {
+                '\\' if index + 1 == chars.len() => {
+                    return Some("the pattern ends with a backslash, so it never matches")
+                }
+                '\\' => index += 1,
+                '[' if bracket_expression(&chars[index..]).is_none() => {
+                    return Some("the pattern has a [ without a matching ]")
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+        None
+    }
+    /// Translates the rule's pattern into a regular expression that matches the
+    /// `/`-separated paths, relative to the `.gitignore` file's directory, that
+    /// the pattern matches. Negation and the directory-only flag are not included.
+    pub fn to_regex(&self) -> String {
+        let mut regex = String::from("^");
+        if !self.is_anchored {
+            regex.push_str("(?:.*/)?");
+        }
+        let chars: Vec<char> = self.pattern.chars().collect();
+        let mut index = 0;
+        while index < chars.len() {
+            let at_segment_start = index == 0 || chars[index - 1] == '/';
+            match chars[index] {
+                '*' if chars.get(index + 1) == Some(&'*') && at_segment_start => {
+                    match chars.get(index + 2) {
+                        // Leading "**/" or "/**/" matches zero or more directories
+                        Some('/') => {
+                            regex.push_str("(?:.*/)?");
+                            index += 3;
+                        }
+                        // Trailing "/**" matches everything inside
+                        None => {
+                            regex.push_str(".*");
+                            index += 2;
+                        }
+                        _ => {
+                            regex.push_str("[^/]*");
+                            index += 2;
+                        }
+                    }
+                    continue;
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                '[' => match bracket_expression(&chars[index..]) {
+                    Some((class, length)) => {
+                        regex.push_str(&class);
+                        index += length;
+                        continue;
+                    }
+                    None => regex.push_str(r"\["),
+                },
+                '\\' if index + 1 < chars.len() => {
+                    index += 1;
+                    regex.push_str(&regex::escape(&chars[index].to_string()));
+                }
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+            index += 1;
+        }
+        regex.push('$');
+        regex
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_negated {
+            write!(f, "!")?;
+        }
+        if self.is_anchored && !self.pattern.contains('/') {
+            write!(f, "/")?;
+        }
+        write!(f, "{}", self.pattern)?;
+        if self.is_directory_only {
+            write!(f, "/")?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes trailing spaces, unless they are escaped with a backslash.
+fn trim_trailing_spaces(text: &str) -> &str {
+    let mut text = text;
+    while text.ends_with(' ') && !text.ends_with("\\ ") {
+        text = &text[..text.len() - 1];
+    }
+    text
+}
+/// Translates the bracket expression at the start of `chars` into a regular
+/// expression character class, and returns it with the length of the expression.
+/// Returns `None` if the bracket expression isn't closed.
+fn bracket_expression(chars: &[char]) -> Option<(String, usize)> {
+    let mut class = String::from("[");
+    let mut index = 1;
+    if matches!(chars.get(index), Some('!') | Some('^')) {
+        class.push_str("^/");
+        index += 1;
+    }
+    let start = index;
+    loop {
+        match chars.get(index)? {
+            ']' if index > start => break,
+            '\\' => {
+                index += 1;
+                let c = *chars.get(index)?;
+                if !c.is_alphanumeric() {
+                    class.push('\\');
+                }
+                class.push(c);
+            }
+            c @ ('[' | ']' | '^' | '&' | '~') => {
+                class.push('\\');
+                class.push(*c);
+            }
+            c => class.push(*c),
+        }
+        index += 1;
+    }
+    class.push(']');
+    Some((class, index + 1))
+}
+/// Returns the name in a `### Name ###` section heading.
+fn section_name(text: &str) -> Option<String> {
+    let name = text
+        .trim_end()
+        .strip_prefix("###")?
+        .strip_suffix("###")?
+        .trim();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(text: &str) -> Rule {
+        Rule::parse(text).unwrap()
+    }
+    fn matches(pattern: &str, path: &str) -> bool {
+        regex::Regex::new(&rule(pattern).to_regex())
+            .unwrap()
+            .is_match(path)
+    }
+
+    #[test]
+    fn round_trip() {
+        for text in [
+            "",
+            "\n",
+            "target/\n",
+            "target/",
+            "# Rust\r\ntarget/\r\n",
+            "a\n\n\nb\n\n",
+            "foo\\ \nbar   \n",
+        ] {
+            assert_eq!(Gitignore::parse(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn empty_input_has_no_lines() {
+        assert!(Gitignore::parse("").lines().is_empty());
+        assert_eq!(Gitignore::parse("\n").lines().len(), 1);
+    }
+
+    #[test]
+    fn crlf_lines() {
+        let gitignore = Gitignore::parse("# Logs\r\n*.log\r\n\r\nbuild/\r\n");
+        let kinds: Vec<_> = gitignore.lines().iter().map(|line| line.kind()).collect();
+        assert_eq!(kinds[0], &LineKind::Comment);
+        assert_eq!(kinds[2], &LineKind::Blank);
+        assert_eq!(gitignore.lines()[1].rule().unwrap().pattern(), "*.log");
+        let build = gitignore.lines()[3].rule().unwrap();
+        assert_eq!(build.pattern(), "build");
+        assert!(build.is_directory_only());
+    }
+
+    #[test]
+    fn sections() {
+        let gitignore =
+            Gitignore::parse("a\n### Rust ###\ntarget/\n# End of https://example.com\nb\n");
+        let sections: Vec<_> = gitignore
+            .lines()
+            .iter()
+            .map(|line| line.section())
+            .collect();
+        assert_eq!(sections, [None, Some("Rust"), Some("Rust"), None, None]);
+    }
+
+    #[test]
+    fn trailing_spaces() {
+        assert_eq!(rule("foo   ").pattern(), "foo");
+        assert_eq!(rule("foo\\ ").pattern(), "foo\\ ");
+        assert!(matches("foo\\ ", "foo "));
+        assert!(!matches("foo\\ ", "foo"));
+        assert_eq!(Rule::parse("   "), None);
+    }
+
+    #[test]
+    fn flags() {
+        let negated = rule("!important.log");
+        assert!(negated.is_negated());
+        assert_eq!(negated.pattern(), "important.log");
+        assert!(!negated.is_anchored());
+
+        let anchored = rule("/build");
+        assert!(anchored.is_anchored());
+        assert!(!anchored.is_directory_only());
+        assert_eq!(anchored.pattern(), "build");
+
+        let directory = rule("build/");
+        assert!(directory.is_directory_only());
+        assert!(!directory.is_anchored());
+        assert_eq!(directory.pattern(), "build");
+
+        let nested = rule("a/b");
+        assert!(nested.is_anchored());
+        assert_eq!(nested.pattern(), "a/b");
+
+        let escaped = rule("\\#notes");
+        assert!(!escaped.is_negated());
+        assert_eq!(escaped.pattern(), "\\#notes");
+        assert!(matches("\\#notes", "#notes"));
+        assert_eq!(LineKind::parse("#notes"), LineKind::Comment);
+
+        let escaped = rule("\\!keep");
+        assert!(!escaped.is_negated());
+        assert!(matches("\\!keep", "!keep"));
+
+        assert_eq!(Rule::parse("/"), None);
+        assert_eq!(Rule::parse("!"), None);
+    }
+
+    #[test]
+    fn display() {
+        for text in ["!important.log", "/build", "build/", "a/b", "!/dist/"] {
+            assert_eq!(rule(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn unanchored_patterns_match_at_any_depth() {
+        assert!(matches("*.log", "a.log"));
+        assert!(matches("*.log", "logs/a.log"));
+        assert!(!matches("*.log", "a.log.txt"));
+        assert!(matches("/build", "build"));
+        assert!(!matches("/build", "src/build"));
+        assert!(matches("a/b", "a/b"));
+        assert!(!matches("a/b", "x/a/b"));
+        assert!(matches("?.txt", "a.txt"));
+        assert!(!matches("?.txt", "/.txt"));
+        assert!(!matches("a*b", "a/b"));
+    }
+
+    #[test]
+    fn double_asterisks() {
+        // **/ matches in all directories
+        assert!(matches("**/foo", "foo"));
+        assert!(matches("**/foo", "a/b/foo"));
+        assert!(matches("**/foo/bar", "a/foo/bar"));
+        // /** matches everything inside
+        assert!(matches("abc/**", "abc/x"));
+        assert!(matches("abc/**", "abc/x/y"));
+        assert!(!matches("abc/**", "abc"));
+        assert!(!matches("abc/**", "x/abc/y"));
+        // /**/ matches zero or more directories
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(!matches("a/**/b", "a/xb"));
+        // Other ** are like *
+        assert!(matches("a**b", "axxb"));
+        assert!(!matches("a**b", "a/b"));
+    }
+
+    #[test]
+    fn bracket_expressions() {
+        assert!(matches("*.[oa]", "x.o"));
+        assert!(!matches("*.[oa]", "x.c"));
+        assert!(matches("[a-c].txt", "b.txt"));
+        assert!(matches("[!a].txt", "b.txt"));
+        assert!(!matches("[!a].txt", "a.txt"));
+        assert!(!matches("x[!a]y", "x/y"));
+        assert!(matches("[^a].txt", "b.txt"));
+        // A ] first in the class is part of it
+        assert!(matches("[]a].txt", "].txt"));
+        assert!(matches("[]a].txt", "a.txt"));
+        assert!(matches("[!]a].txt", "b.txt"));
+        assert!(!matches("[!]a].txt", "].txt"));
+        // An unclosed [ is matched literally
+        assert!(matches("[abc", "[abc"));
+        assert!(rule("[abc").error().is_some());
+        assert!(rule("foo\\").error().is_some());
+        assert_eq!(rule("[]a]").error(), None);
+    }
+}
@@ -1,10 +1,39 @@
+use crate::builtin;
+use crate::cache::{self, Validators};
+use crate::client::Client;
+use crate::config;
+use crate::error::{Error, Result};
+use crate::http;
+use crate::lock;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
 const API_URL: &str = "https://www.toptal.com/developers/gitignore/api";
 
-pub(crate) fn get_template_names() -> Result<Vec<String>, minreq::Error> {
-    let url = format!("{API_URL}/list");
-    let response = minreq::get(&url).send()?;
+/// Returns the configured API URL, or the gitignore.io API's URL.
+fn api_url() -> &'static str {
+    config::get()
+        .api_url
+        .as_deref()
+        .map(|url| url.trim_end_matches('/'))
+        .unwrap_or(API_URL)
+}
+
+pub(crate) fn get_template_names(client: &Client) -> Result<Vec<String>> {
+    let url = format!("{}/list", api_url());
+    let text = match get_cached(client, &url, "list.txt", "text/plain") {
+        Ok((text, _)) => text,
+        Err(error) if use_builtin(client, &error) => {
+            return Ok(builtin::templates()?
+                .into_iter()
+                .map(|template| template.key)
+                .collect())
+        }
+        Err(error) => return Err(error),
+    };
     let mut vec = Vec::new();
-    for lines in response.as_str()?.split('\n') {
+    for lines in text.split('\n') {
         for template in lines.split(',') {
             vec.push(template.to_string());
         }
@@ -12,7 +41,191 @@ pub(crate) fn get_template_names() -> Result<Vec<String>, minreq::Error> {
     Ok(vec)
 }
 
-pub(crate) fn get_template(template_names: &[String]) -> Result<String, minreq::Error> {
-    let url = format!("{API_URL}/{}", template_names.join(","));
-    Ok(minreq::get(url).send()?.as_str()?.to_string())
+pub(crate) fn get_template(client: &Client, template_names: &[String]) -> Result<String> {
+    let url = format!("{}/{}", api_url(), template_names.join(","));
+    // The names can be too long for a file name
+    let cache_name = format!(
+        "template-{}.txt",
+        &lock::hash(&url)["sha256:".len()..][..16]
+    );
+    match get_cached(client, &url, &cache_name, "text/plain") {
+        Ok((text, _)) => Ok(text),
+        Err(error) if use_builtin(client, &error) => builtin::generate(&url, template_names),
+        Err(error) => Err(error),
+    }
+}
+
+/// Returns true if the built-in templates are used after `error`: when the API can't be
+/// reached and nothing has been cached, unless the templates have to be current.
+fn use_builtin(client: &Client, error: &Error) -> bool {
+    builtin::is_available()
+        && !client.require_current
+        && matches!(error, Error::Network(_) | Error::HttpStatus { .. })
+}
+/// Returns the older cached file after a failed request, unless the templates have to be current.
+fn use_stale(client: &Client, cached_text: Option<String>, error: Error) -> Result<(String, bool)> {
+    match cached_text {
+        Some(text) if !client.require_current => Ok((text, true)),
+        _ => Err(error),
+    }
+}
+
+/// Returns the contents of `url`, and true if they came from the cached file `cache_name`.
+///
+/// The cached file is used if it's newer than the maximum age. Otherwise it's checked with a
+/// conditional request, and only fetched again if it has changed. If the request fails, an
+/// older cached file is used, unless the templates have to be current.
+fn get_cached(
+    client: &Client,
+    url: &str,
+    cache_name: &str,
+    content_type: &str,
+) -> Result<(String, bool)> {
+    let max_age = cache::max_age(client.refresh || client.require_current);
+    if let Some(text) = cache::read(cache_name, max_age) {
+        return Ok((text, true));
+    }
+    let cached_text = cache::read(cache_name, Duration::MAX);
+    let validators = match cached_text {
+        Some(_) => cache::read_validators(cache_name),
+        None => Validators::default(),
+    };
+    let mut headers = Vec::new();
+    if let Some(etag) = &validators.etag {
+        headers.push(("If-None-Match", etag.as_str()));
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        headers.push(("If-Modified-Since", last_modified.as_str()));
+    }
+    let response = match http::send(url, &headers, &client.proxy_environment) {
+        Ok(response) => response,
+        Err(error) => return use_stale(client, cached_text, error),
+    };
+    if response.status_code == 304 {
+        if let Some(text) = cached_text {
+            // Start the maximum age over
+            cache::write(cache_name, &text);
+            return Ok((text, true));
+        }
+    }
+    // The API answers with 404 and an error line if a template doesn't exist
+    if response.status_code == 404 {
+        let re = Regex::new(r"#!! ERROR: (\S+) is undefined").unwrap();
+        if let Some(captures) = re.captures(response.as_str().unwrap_or_default()) {
+            return Err(Error::UnknownTemplate(captures[1].to_string()));
+        }
+    }
+    let text = match http::check_response(url, response, content_type) {
+        Ok(response) => {
+            let text = response.as_str()?.to_string();
+            let validators = Validators {
+                etag: response.headers.get("etag").cloned(),
+                last_modified: response.headers.get("last-modified").cloned(),
+            };
+            cache::write(cache_name, &text);
+            cache::write_validators(cache_name, &validators);
+            text
+        }
+        Err(error) => return use_stale(client, cached_text, error),
+    };
+    Ok((text, false))
+}
+
+/// A template, as listed by `list?format=json`.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct TemplateInfo {
+    pub key: String,
+    /// The name used in the template's `### Name ###` heading
+    pub name: String,
+    pub contents: String,
+    /// Where the template came from
+    #[serde(skip)]
+    pub source: String,
+    /// True if the template was read from the local cache
+    #[serde(skip)]
+    pub cached: bool,
+}
+
+/// Returns all of the available templates, including their contents, ordered by name.
+///
+/// The templates are cached for a day, unless the configuration says otherwise. If they can't be fetched, an older cached copy is used,
+/// or the built-in templates if the program has them.
+pub(crate) fn get_templates(client: &Client) -> Result<Vec<TemplateInfo>> {
+    const CACHE_NAME: &str = "list.json";
+    let url = format!("{}/list?format=json", api_url());
+    let (json, cached) = match get_cached(client, &url, CACHE_NAME, "application/json") {
+        Ok(result) => result,
+        Err(error) if use_builtin(client, &error) => return builtin::templates(),
+        Err(error) => return Err(error),
+    };
+    match parse_templates(&json, api_url(), cached) {
+        Ok(templates) => Ok(templates),
+        Err(error) if cached => Err(Error::Cache {
+            path: cache::path(CACHE_NAME).unwrap_or_default(),
+            message: error.to_string(),
+        }),
+        Err(error) => Err(Error::parse(url, error)),
+    }
+}
+/// Reads the templates in the API's `list?format=json` format, ordered by name.
+pub(crate) fn parse_templates(
+    json: &str,
+    source: &str,
+    cached: bool,
+) -> serde_json::Result<Vec<TemplateInfo>> {
+    let templates: BTreeMap<String, TemplateInfo> = serde_json::from_str(json)?;
+    Ok(templates
+        .into_values()
+        .map(|template| TemplateInfo {
+            source: source.to_string(),
+            cached,
+            ..template
+        })
+        .collect())
+}
+
+/// Returns a `.gitignore` file generated from the named `templates`, in the same format
+/// as the API's response to `url`.
+pub(crate) fn generate(
+    url: &str,
+    templates: &[TemplateInfo],
+    template_names: &[String],
+) -> Result<String> {
+    let mut text = format!(
+        "# Created by {}\n# Edit at https://www.toptal.com/developers/gitignore?templates={}\n",
+        url,
+        template_names.join(",")
+    );
+    for name in template_names {
+        match find_template(templates, name) {
+            Some(template) => text += template.contents.as_str(),
+            None => return Err(Error::UnknownTemplate(name.clone())),
+        }
+    }
+    text += format!("\n# End of {}\n", url).as_str();
+    Ok(text)
+}
+/// Returns the template named `name`, ignoring case.
+pub fn find_template<'a>(templates: &'a [TemplateInfo], name: &str) -> Option<&'a TemplateInfo> {
+    templates
+        .iter()
+        .find(|template| template.key.eq_ignore_ascii_case(name))
+}
+
+/// Returns the names of the templates recorded in the `# Created by .../api/<names>`
+/// and `# End of .../api/<names>` lines of a previously generated `.gitignore` file.
+pub fn parse_template_names(gitignore: &str) -> Vec<String> {
+    let re = Regex::new(r"^# (?:Created by|End of) https?://\S*/api/(\S+)$").unwrap();
+    let mut vec: Vec<String> = Vec::new();
+    for line in gitignore.lines() {
+        if let Some(captures) = re.captures(line.trim_end()) {
+            for template in captures[1].split(',') {
+                let template = template.to_lowercase();
+                if !template.is_empty() && !vec.contains(&template) {
+                    vec.push(template);
+                }
+            }
+        }
+    }
+    vec
 }
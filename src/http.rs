@@ -0,0 +1,292 @@
+use crate::cache;
+use crate::config;
+use crate::error::{Error, Result};
+use crate::lock;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// How many seconds a whole request can take, unless the configuration says otherwise.
+const DEFAULT_TIMEOUT: u64 = 30;
+/// How many times a request is tried again, unless the configuration says otherwise.
+const DEFAULT_RETRIES: u32 = 3;
+/// How long to wait before trying a request again the first time. The wait doubles after each try.
+const FIRST_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// The longest a `Retry-After` header can make a request wait before it gives up.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Sends a GET request with `headers`, trying again after failures that may go away: network
+/// errors, 429 Too Many Requests and 5xx statuses. Returns the last response, whatever its status.
+pub(crate) fn send(
+    url: &str,
+    headers: &[(&str, &str)],
+    environment: &ProxyEnvironment,
+) -> Result<minreq::Response> {
+    let config = &config::get().network;
+    let proxy = proxy(url, environment)?;
+    let retries = config.retries.unwrap_or(DEFAULT_RETRIES);
+    let mut delay = FIRST_RETRY_DELAY;
+    let mut attempt = 0;
+    loop {
+        let mut request = minreq::get(url)
+            .with_timeout(config.timeout.unwrap_or(DEFAULT_TIMEOUT))
+            .with_headers(headers.iter().copied());
+        if let Some(proxy) = &proxy {
+            request = request.with_proxy(proxy.clone());
+        }
+        let result = request.send();
+        let wait = match &result {
+            Err(minreq::Error::IoError(_)) => delay,
+            Ok(response) if response.status_code == 429 || response.status_code >= 500 => {
+                match retry_after(response) {
+                    Some(wait) if wait > MAX_RETRY_AFTER => return Ok(result?),
+                    Some(wait) => wait,
+                    None => delay,
+                }
+            }
+            _ => return Ok(result?),
+        };
+        if attempt == retries {
+            return Ok(result?);
+        }
+        std::thread::sleep(wait);
+        delay *= 2;
+        attempt += 1;
+    }
+}
+/// Returns how long a `Retry-After` header asks to wait. Only a number of seconds is understood.
+fn retry_after(response: &minreq::Response) -> Option<Duration> {
+    let seconds = response.headers.get("retry-after")?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+/// Fails if a response doesn't have a success status or isn't `content_type`.
+pub(crate) fn check_response(
+    url: &str,
+    response: minreq::Response,
+    content_type: &str,
+) -> Result<minreq::Response> {
+    if !(200..300).contains(&response.status_code) {
+        return Err(Error::HttpStatus {
+            url: url.to_string(),
+            status: response.status_code,
+            reason: response.reason_phrase.clone(),
+        });
+    }
+    // An error page from a proxy would otherwise end up in the .gitignore file
+    if let Some(header) = response.headers.get("content-type") {
+        let mime_type = header.split(';').next().unwrap_or_default().trim();
+        if !mime_type.eq_ignore_ascii_case(content_type) {
+            return Err(Error::parse(
+                url,
+                format!("expected {} but got {}", content_type, mime_type),
+            ));
+        }
+    }
+    Ok(response)
+}
+
+/// The proxy settings from the environment variables curl uses. The default settings don't
+/// have any proxies.
+#[derive(Clone, Debug, Default)]
+pub struct ProxyEnvironment {
+    https_proxy: Option<String>,
+    http_proxy: Option<String>,
+    all_proxy: Option<String>,
+    no_proxy: Vec<String>,
+}
+
+impl ProxyEnvironment {
+    /// The variables minreq reads a proxy from itself when a request doesn't have one.
+    const VARIABLES: [&'static str; 5] = [
+        "https_proxy",
+        "HTTPS_PROXY",
+        "http_proxy",
+        "all_proxy",
+        "ALL_PROXY",
+    ];
+
+    /// Reads the proxy settings from the environment.
+    pub fn from_env() -> Self {
+        let var = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        };
+        ProxyEnvironment {
+            https_proxy: var(&["https_proxy", "HTTPS_PROXY"]),
+            // HTTP_PROXY isn't used, because a web server can set it for a CGI program
+            http_proxy: var(&["http_proxy"]),
+            all_proxy: var(&["all_proxy", "ALL_PROXY"]),
+            no_proxy: ["no_proxy", "NO_PROXY"]
+                .iter()
+                .find_map(|name| std::env::var(name).ok())
+                .map(|value| value.split(',').map(|host| host.to_string()).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The proxy settings that were in the environment before [`take_proxy_environment`] took
+/// them out of it.
+static PROXY_ENVIRONMENT: OnceLock<ProxyEnvironment> = OnceLock::new();
+/// The CA bundle [`trust_ca_bundle`] has added to the certificates minreq trusts.
+static CA_BUNDLE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Returns the proxy settings in the environment.
+///
+/// minreq uses the proxy variables itself when a request doesn't go through a proxy, so it
+/// would send requests to the hosts in `NO_PROXY` through a proxy too. The first time, the
+/// variables are taken out of the process's environment, so that the proxy each request is
+/// sent through is only the one [`send`] gives it.
+pub(crate) fn take_proxy_environment() -> ProxyEnvironment {
+    PROXY_ENVIRONMENT
+        .get_or_init(|| {
+            let environment = ProxyEnvironment::from_env();
+            for name in ProxyEnvironment::VARIABLES {
+                std::env::remove_var(name);
+            }
+            environment
+        })
+        .clone()
+}
+/// Adds the certificates in the PEM file at `path` to the ones minreq trusts.
+///
+/// minreq reads its certificates once, when it first connects with HTTPS: the ones built into
+/// webpki-roots, and the system's, which rustls-native-certs reads from the file in
+/// `SSL_CERT_FILE` instead if it's set. It can't be given any others, so the system's
+/// certificates and the bundle's are written to one file in the user's cache directory, and
+/// `SSL_CERT_FILE` is set to it. Only one bundle can be added in a process.
+pub(crate) fn trust_ca_bundle(path: &Path) -> Result<()> {
+    let mut trusted = CA_BUNDLE.lock().unwrap_or_else(|error| error.into_inner());
+    match trusted.as_deref() {
+        Some(trusted) if trusted == path => return Ok(()),
+        Some(trusted) => {
+            return Err(Error::Invalid(format!(
+                "Only one CA bundle can be used, and {} is already trusted",
+                trusted.display()
+            )))
+        }
+        None => {}
+    }
+    let bundle = std::fs::read(path).map_err(|error| Error::io(path, error))?;
+    let certificates = rustls_pemfile::certs(&mut bundle.as_slice())
+        .map_err(|error| Error::parse(path.display(), error))?;
+    if certificates.is_empty() {
+        return Err(Error::parse(
+            path.display(),
+            "there are no PEM certificates in it",
+        ));
+    }
+    // Read before SSL_CERT_FILE is changed, so they're the system's, or the ones in the
+    // file it was already set to
+    let system_certificates = rustls_native_certs::load_native_certs().unwrap_or_default();
+    let text: String = system_certificates
+        .iter()
+        .map(|certificate| certificate.0.as_slice())
+        .chain(certificates.iter().map(Vec::as_slice))
+        .map(pem)
+        .collect();
+    let dir = cache::user_dir().ok_or_else(|| {
+        Error::Invalid("There's no cache directory to write the CA bundle to".to_string())
+    })?;
+    // Named after its contents, so that programs with different bundles don't replace each
+    // other's file before minreq reads it
+    let combined = dir.join(format!(
+        "ca-bundle-{}.pem",
+        &lock::hash(&text)["sha256:".len()..][..16]
+    ));
+    let partial = combined.with_extension(format!("pem.{}", std::process::id()));
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&partial, &text))
+        .and_then(|_| std::fs::rename(&partial, &combined))
+        .map_err(|error| Error::io(&combined, error))?;
+    std::env::set_var("SSL_CERT_FILE", &combined);
+    *trusted = Some(path.to_path_buf());
+    Ok(())
+}
+/// Returns a DER certificate in PEM format.
+fn pem(der: &[u8]) -> String {
+    let base64 = STANDARD.encode(der);
+    let lines: Vec<&str> = base64
+        .as_bytes()
+        .chunks(64)
+        .map(|line| std::str::from_utf8(line).expect("Base64 is ASCII"))
+        .collect();
+    format!(
+        "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+        lines.join("\n")
+    )
+}
+/// Returns the proxy to send a request to `url` through, if there is one. The configured
+/// proxy is used before the one in `environment`.
+fn proxy(url: &str, environment: &ProxyEnvironment) -> Result<Option<minreq::Proxy>> {
+    let config = &config::get().network;
+    let no_proxy = config.no_proxy.as_ref().unwrap_or(&environment.no_proxy);
+    let host = host(url);
+    if no_proxy.iter().any(|pattern| matches_host(pattern, &host)) {
+        return Ok(None);
+    }
+    let proxy = config
+        .proxy
+        .as_ref()
+        .or(if url.starts_with("https://") {
+            environment.https_proxy.as_ref()
+        } else {
+            environment.http_proxy.as_ref()
+        })
+        .or(environment.all_proxy.as_ref());
+    match proxy {
+        Some(proxy) => match minreq::Proxy::new(proxy) {
+            Ok(proxy) => Ok(Some(proxy)),
+            // The proxy's URL isn't shown, because it can have a password in it
+            Err(_) => Err(Error::Invalid(
+                "The proxy isn't an http://[user[:password]@]host[:port] URL".to_string(),
+            )),
+        },
+        None => Ok(None),
+    }
+}
+/// Returns the host name in `url`, in lowercase.
+fn host(url: &str) -> String {
+    let authority = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = authority.split(['/', '?']).next().unwrap_or_default();
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host_port)| host_port);
+    let host = match host_port.strip_prefix('[') {
+        // An IPv6 address
+        Some(address) => address.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    };
+    host.to_lowercase()
+}
+/// Returns true if `host` is matched by a `NO_PROXY` entry: `*`, a host name, or a
+/// domain name that matches the domain and its subdomains.
+fn matches_host(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    if pattern == "*" {
+        return true;
+    }
+    let pattern = pattern.trim_start_matches("*.").trim_start_matches('.');
+    !pattern.is_empty()
+        && (host == pattern
+            || host
+                .strip_suffix(pattern)
+                .is_some_and(|subdomain| subdomain.ends_with('.')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pem_round_trips() {
+        let der: Vec<u8> = (0..=255).collect();
+        let pem = pem(&der);
+        assert!(pem.lines().all(|line| line.len() <= 64));
+        let certificates = rustls_pemfile::certs(&mut pem.as_bytes()).unwrap();
+        assert_eq!(certificates, vec![der]);
+    }
+}
@@ -0,0 +1,1068 @@
+//! The interactive app, which selects templates from lists and saves the `.gitignore` file.
+
+use cursive::{
+    align::HAlign,
+    backend::Backend,
+    event::{Event, EventResult, Key},
+    menu,
+    style::{
+        BaseColor, BorderStyle, Color, ColorStyle, Effect, Palette, PaletteColor, PaletteStyle,
+        Style,
+    },
+    theme::Theme,
+    traits::*,
+    utils::markup::StyledString,
+    views::{Dialog, DummyView, LinearLayout, OnEventView, SelectView, TextView},
+    Cursive,
+};
+use gig_gen::internal::block;
+use gig_gen::internal::config::{self, FilterMode};
+use gig_gen::internal::format;
+use gig_gen::internal::git;
+use gig_gen::internal::gitignore::Gitignore;
+use gig_gen::internal::lock;
+use gig_gen::{Client, Format, Result, SaveMode, TemplateInfo, Templates};
+use std::path::{Path, PathBuf};
+
+const AVAILABLE_VIEW_NAME: &str = "available";
+const SELECTED_VIEW_NAME: &str = "selected";
+const FILTER_VIEW_NAME: &str = "filter";
+
+type CbSink = crossbeam_channel::Sender<Box<dyn FnOnce(&mut Cursive) + Send>>;
+
+/// Where the app gets its templates from.
+pub trait TemplateSource {
+    /// Returns the names of the available templates.
+    fn names(&self) -> Result<Vec<String>>;
+    /// Returns a `.gitignore` file generated from the named templates.
+    fn fetch(&self, template_names: &[String]) -> Result<String>;
+    /// Returns the available templates, including their contents, to record in the lockfile.
+    fn templates(&self) -> Result<Vec<TemplateInfo>>;
+}
+
+/// The templates from the API.
+impl TemplateSource for Client {
+    fn names(&self) -> Result<Vec<String>> {
+        self.list_templates()
+    }
+    fn fetch(&self, template_names: &[String]) -> Result<String> {
+        Client::fetch(self, template_names)
+    }
+    fn templates(&self) -> Result<Vec<TemplateInfo>> {
+        Client::templates(self)
+    }
+}
+
+struct UserData {
+    templates: Templates,
+    filter: String,
+    new_filter: bool,
+    cb_sink: CbSink,
+    final_message: Option<String>,
+    format: Format,
+    source: Box<dyn TemplateSource>,
+    /// The directory the file is written in
+    dir: PathBuf,
+}
+
+impl UserData {
+    fn output_path(&self) -> PathBuf {
+        self.dir.join(config::output_file(self.format))
+    }
+}
+
+/// Runs the app on `backend` with the templates from `source`, saving the file in `dir`.
+/// Returns the message to show after the app closes.
+pub fn run(
+    backend: Box<dyn Backend>,
+    source: Box<dyn TemplateSource>,
+    dir: &Path,
+) -> Option<String> {
+    let mut siv = Cursive::new();
+    setup(&mut siv, source, dir);
+    siv.runner(backend).run();
+    siv.with_user_data(|user_data: &mut UserData| user_data.final_message.take())
+        .flatten()
+}
+/// Adds the app's views and callbacks to `siv`.
+fn setup(siv: &mut Cursive, source: Box<dyn TemplateSource>, dir: &Path) {
+    fn load_templates(source: &dyn TemplateSource, dir: &Path) -> Templates {
+        let mut templates = Templates::new();
+        if let Ok(template_names) = source.names() {
+            templates.set_list(template_names);
+        };
+        // Pre-select the templates an existing .gitignore file was generated from,
+        // or the default templates
+        let gitignore = std::fs::read_to_string(dir.join(config::output_file(Format::Gitignore)))
+            .unwrap_or_default();
+        let mut template_names = gig_gen::parse_template_names(&gitignore);
+        if template_names.is_empty() {
+            template_names = config::get().templates.clone().unwrap_or_default();
+        }
+        templates.select_templates(&template_names);
+        templates
+        // TODO: Handle error?
+    }
+    siv.add_global_callback(Event::CtrlChar('q'), |siv| siv.quit());
+    siv.add_global_callback(Event::CtrlChar('s'), save);
+    siv.add_global_callback(Event::Key(Key::F1), help);
+    let user_data = UserData {
+        templates: load_templates(source.as_ref(), dir),
+        filter: String::default(),
+        new_filter: false,
+        cb_sink: siv.cb_sink().clone(),
+        final_message: None,
+        format: Format::Gitignore,
+        source,
+        dir: dir.to_path_buf(),
+    };
+    siv.set_user_data(user_data);
+    siv.set_theme(theme());
+
+    siv.menubar()
+        .add_subtree(
+            "File",
+            menu::Tree::new().with(|tree| {
+                tree.add_leaf("Save ^S", save);
+                tree.add_leaf("Save as...", save_as);
+                tree.add_leaf("Quit ^Q", Cursive::quit);
+            }),
+        )
+        .add_subtree(
+            "Help",
+            menu::Tree::new().with(|tree| {
+                tree.add_leaf("Help F1", help);
+                tree.add_leaf("About", about);
+            }),
+        );
+    siv.set_autohide_menu(false);
+
+    siv.add_fullscreen_layer(event_view(main_layer()));
+    refresh(siv);
+}
+fn theme() -> Theme {
+    Theme {
+        shadow: false,
+        borders: BorderStyle::Simple,
+        palette: Palette::retro().with(|palette| {
+            palette[PaletteColor::Background] = Color::TerminalDefault;
+            palette[PaletteColor::View] = BaseColor::Black.dark();
+            palette[PaletteColor::Primary] = BaseColor::White.light();
+            palette[PaletteColor::Secondary] = BaseColor::Blue.light();
+            palette[PaletteColor::Tertiary] = BaseColor::Yellow.light();
+            palette[PaletteColor::Highlight] = BaseColor::Blue.dark();
+            palette[PaletteColor::HighlightText] = BaseColor::White.light();
+            palette[PaletteStyle::TitlePrimary] =
+                Style::from(BaseColor::Yellow.light()).combine(Effect::Bold);
+            palette[PaletteStyle::HighlightInactive] = Style::from(ColorStyle::new(
+                BaseColor::White.light(),
+                BaseColor::Black.light(),
+            ));
+        }),
+    }
+}
+fn event_view(content: impl View) -> impl View {
+    fn clear_filter(siv: &mut Cursive) {
+        if let Some(user_data) = siv.user_data::<UserData>() {
+            user_data.filter = String::default();
+            user_data.new_filter = true;
+        }
+        refresh(siv);
+    }
+    fn backspace(siv: &mut Cursive) {
+        if let Some(user_data) = siv.user_data::<UserData>() {
+            if !user_data.filter.is_empty() {
+                user_data.filter = user_data.filter[..user_data.filter.len() - 1].to_string();
+                user_data.new_filter = true;
+            }
+        }
+        refresh(siv);
+    }
+    fn handle_char(siv: &mut Cursive, c: char) {
+        if let Some(user_data) = siv.user_data::<UserData>() {
+            user_data.filter += c.to_string().as_str();
+            user_data.new_filter = true;
+        }
+        refresh(siv);
+    }
+    OnEventView::new(content)
+        .on_event(Event::Key(Key::Esc), clear_filter)
+        .on_event(Event::Key(Key::Backspace), backspace)
+        .on_event(Event::Char('a'), |s| handle_char(s, 'a'))
+        .on_event(Event::Char('b'), |s| handle_char(s, 'b'))
+        .on_event(Event::Char('c'), |s| handle_char(s, 'c'))
+        .on_event(Event::Char('d'), |s| handle_char(s, 'd'))
+        .on_event(Event::Char('e'), |s| handle_char(s, 'e'))
+        .on_event(Event::Char('f'), |s| handle_char(s, 'f'))
+        .on_event(Event::Char('g'), |s| handle_char(s, 'g'))
+        .on_event(Event::Char('h'), |s| handle_char(s, 'h'))
+        .on_event(Event::Char('i'), |s| handle_char(s, 'i'))
+        .on_event(Event::Char('j'), |s| handle_char(s, 'j'))
+        .on_event(Event::Char('k'), |s| handle_char(s, 'k'))
+        .on_event(Event::Char('l'), |s| handle_char(s, 'l'))
+        .on_event(Event::Char('m'), |s| handle_char(s, 'm'))
+        .on_event(Event::Char('n'), |s| handle_char(s, 'n'))
+        .on_event(Event::Char('o'), |s| handle_char(s, 'o'))
+        .on_event(Event::Char('p'), |s| handle_char(s, 'p'))
+        .on_event(Event::Char('q'), |s| handle_char(s, 'q'))
+        .on_event(Event::Char('r'), |s| handle_char(s, 'r'))
+        .on_event(Event::Char('s'), |s| handle_char(s, 's'))
+        .on_event(Event::Char('t'), |s| handle_char(s, 't'))
+        .on_event(Event::Char('u'), |s| handle_char(s, 'u'))
+        .on_event(Event::Char('v'), |s| handle_char(s, 'v'))
+        .on_event(Event::Char('w'), |s| handle_char(s, 'w'))
+        .on_event(Event::Char('x'), |s| handle_char(s, 'x'))
+        .on_event(Event::Char('y'), |s| handle_char(s, 'y'))
+        .on_event(Event::Char('z'), |s| handle_char(s, 'z'))
+        .on_event(Event::Char('A'), |s| handle_char(s, 'A'))
+        .on_event(Event::Char('B'), |s| handle_char(s, 'B'))
+        .on_event(Event::Char('C'), |s| handle_char(s, 'C'))
+        .on_event(Event::Char('D'), |s| handle_char(s, 'D'))
+        .on_event(Event::Char('E'), |s| handle_char(s, 'E'))
+        .on_event(Event::Char('F'), |s| handle_char(s, 'F'))
+        .on_event(Event::Char('G'), |s| handle_char(s, 'G'))
+        .on_event(Event::Char('H'), |s| handle_char(s, 'H'))
+        .on_event(Event::Char('I'), |s| handle_char(s, 'I'))
+        .on_event(Event::Char('J'), |s| handle_char(s, 'J'))
+        .on_event(Event::Char('K'), |s| handle_char(s, 'K'))
+        .on_event(Event::Char('L'), |s| handle_char(s, 'L'))
+        .on_event(Event::Char('M'), |s| handle_char(s, 'M'))
+        .on_event(Event::Char('N'), |s| handle_char(s, 'N'))
+        .on_event(Event::Char('O'), |s| handle_char(s, 'O'))
+        .on_event(Event::Char('P'), |s| handle_char(s, 'P'))
+        .on_event(Event::Char('Q'), |s| handle_char(s, 'Q'))
+        .on_event(Event::Char('R'), |s| handle_char(s, 'R'))
+        .on_event(Event::Char('S'), |s| handle_char(s, 'S'))
+        .on_event(Event::Char('T'), |s| handle_char(s, 'T'))
+        .on_event(Event::Char('U'), |s| handle_char(s, 'U'))
+        .on_event(Event::Char('V'), |s| handle_char(s, 'V'))
+        .on_event(Event::Char('W'), |s| handle_char(s, 'W'))
+        .on_event(Event::Char('X'), |s| handle_char(s, 'X'))
+        .on_event(Event::Char('Y'), |s| handle_char(s, 'Y'))
+        .on_event(Event::Char('Z'), |s| handle_char(s, 'Z'))
+        .on_event(Event::Char('0'), |s| handle_char(s, '0'))
+        .on_event(Event::Char('1'), |s| handle_char(s, '1'))
+        .on_event(Event::Char('2'), |s| handle_char(s, '2'))
+        .on_event(Event::Char('3'), |s| handle_char(s, '3'))
+        .on_event(Event::Char('4'), |s| handle_char(s, '4'))
+        .on_event(Event::Char('5'), |s| handle_char(s, '5'))
+        .on_event(Event::Char('6'), |s| handle_char(s, '6'))
+        .on_event(Event::Char('7'), |s| handle_char(s, '7'))
+        .on_event(Event::Char('8'), |s| handle_char(s, '8'))
+        .on_event(Event::Char('9'), |s| handle_char(s, '9'))
+        .on_event(Event::Char(' '), |s| handle_char(s, ' '))
+        .on_event(Event::Char('!'), |s| handle_char(s, '!'))
+        .on_event(Event::Char('"'), |s| handle_char(s, '"'))
+        .on_event(Event::Char('#'), |s| handle_char(s, '#'))
+        .on_event(Event::Char('$'), |s| handle_char(s, '$'))
+        .on_event(Event::Char('%'), |s| handle_char(s, '%'))
+        .on_event(Event::Char('\''), |s| handle_char(s, '\''))
+        .on_event(Event::Char('('), |s| handle_char(s, '('))
+        .on_event(Event::Char(')'), |s| handle_char(s, ')'))
+        .on_event(Event::Char('*'), |s| handle_char(s, '*'))
+        .on_event(Event::Char('+'), |s| handle_char(s, '+'))
+        .on_event(Event::Char(','), |s| handle_char(s, ','))
+        .on_event(Event::Char('-'), |s| handle_char(s, '-'))
+        .on_event(Event::Char('.'), |s| handle_char(s, '.'))
+        .on_event(Event::Char('/'), |s| handle_char(s, '/'))
+        .on_event(Event::Char(':'), |s| handle_char(s, ':'))
+        .on_event(Event::Char(';'), |s| handle_char(s, ';'))
+        .on_event(Event::Char('<'), |s| handle_char(s, '<'))
+        .on_event(Event::Char('='), |s| handle_char(s, '='))
+        .on_event(Event::Char('>'), |s| handle_char(s, '>'))
+        .on_event(Event::Char('?'), |s| handle_char(s, '?'))
+        .on_event(Event::Char('\''), |s| handle_char(s, '\''))
+        .on_event(Event::Char('['), |s| handle_char(s, '['))
+        .on_event(Event::Char('\\'), |s| handle_char(s, '\\'))
+        .on_event(Event::Char(']'), |s| handle_char(s, ']'))
+        .on_event(Event::Char('^'), |s| handle_char(s, '^'))
+        .on_event(Event::Char('_'), |s| handle_char(s, '_'))
+        .on_event(Event::Char('`'), |s| handle_char(s, '`'))
+        .on_event(Event::Char('{'), |s| handle_char(s, '{'))
+        .on_event(Event::Char('|'), |s| handle_char(s, '|'))
+        .on_event(Event::Char('}'), |s| handle_char(s, '}'))
+        .on_event(Event::Char('~'), |s| handle_char(s, '~'))
+}
+fn main_layer() -> impl View {
+    fn make_label(text: &str) -> impl View {
+        TextView::new(StyledString::styled(text, BaseColor::Yellow.dark())).h_align(HAlign::Center)
+    }
+    fn make_layout(label: &str, name: &str, on_submit: fn(&mut Cursive, &str)) -> impl View {
+        fn make_select_view(name: &str, on_submit: fn(&mut Cursive, &str)) -> impl View {
+            SelectView::<String>::new()
+                .on_submit(on_submit)
+                .with_name(name)
+                .scrollable()
+                .wrap_with(OnEventView::new)
+                .on_pre_event_inner(Event::CtrlChar('n'), |view, _event| {
+                    view.on_event(Event::Key(Key::Down));
+                    Some(EventResult::Consumed(None))
+                })
+                .on_pre_event_inner(Event::CtrlChar('p'), |view, _event| {
+                    view.on_event(Event::Key(Key::Up));
+                    Some(EventResult::Consumed(None))
+                })
+        }
+        LinearLayout::vertical()
+            .child(make_label(label))
+            .child(make_select_view(name, on_submit))
+            .min_width(29)
+            .full_width()
+            .full_height()
+    }
+
+    let lists_layout = LinearLayout::horizontal()
+        .child(make_layout(
+            " Available templates ",
+            AVAILABLE_VIEW_NAME,
+            select_item,
+        ))
+        .child(DummyView::new().fixed_width(4))
+        .child(make_layout(
+            " Selected templates ",
+            SELECTED_VIEW_NAME,
+            unselect_item,
+        ));
+
+    let filter_layout = LinearLayout::horizontal()
+        .child(make_label("Filter:"))
+        .child(TextView::new(String::default()).with_name(FILTER_VIEW_NAME));
+
+    LinearLayout::vertical()
+        .child(lists_layout)
+        .child(DummyView::new())
+        .child(filter_layout)
+}
+fn save(siv: &mut Cursive) {
+    siv.with_user_data(|user_data: &mut UserData| {
+        if user_data.templates.any_selected() {
+            if user_data.output_path().exists() {
+                user_data
+                    .cb_sink
+                    .send(Box::new(get_overwrite_choice))
+                    .expect("get overwrite choice failed");
+            } else {
+                user_data
+                    .cb_sink
+                    .send(Box::new(create))
+                    .expect("create failed");
+            }
+        } else {
+            user_data
+                .cb_sink
+                .send(Box::new(nothing_to_save_warning))
+                .expect("save warning failed");
+        }
+    });
+}
+fn save_as(siv: &mut Cursive) {
+    let mut select_view = SelectView::new().on_submit(|s, format: &Format| {
+        s.pop_layer();
+        s.with_user_data(|user_data: &mut UserData| user_data.format = *format);
+        save(s);
+    });
+    for format in Format::ALL {
+        select_view.add_item(format.file_name(), format);
+    }
+    siv.add_layer(
+        Dialog::around(select_view)
+            .title("Save as")
+            .button("Cancel", |s| {
+                s.pop_layer();
+            }),
+    );
+}
+fn get_overwrite_choice(siv: &mut Cursive) {
+    let file_name = output_file_name(siv);
+    let path = siv
+        .with_user_data(|user_data: &mut UserData| user_data.output_path())
+        .expect("No user data?");
+    // Only offer to update the generated part of the file if it has one
+    let is_generated = std::fs::read_to_string(path)
+        .is_ok_and(|text| !block::find_blocks(&Gitignore::parse(&text)).is_empty());
+    let mut dialog =
+        Dialog::text(format!("A {} file already\rexists.", file_name)).h_align(HAlign::Center);
+    if is_generated {
+        dialog.add_button("Update", |s| {
+            s.pop_layer();
+            s.user_data::<UserData>()
+                .unwrap()
+                .cb_sink
+                .clone()
+                .send(Box::new(update))
+                .expect("update choice failed");
+        });
+    }
+    siv.add_layer(
+        dialog
+            .button("Overwrite", |s| {
+                s.pop_layer();
+                s.user_data::<UserData>()
+                    .unwrap()
+                    .cb_sink
+                    .clone()
+                    .send(Box::new(overwrite))
+                    .expect("overwrite choice failed");
+            })
+            .button("Append", |s| {
+                s.pop_layer();
+                s.user_data::<UserData>()
+                    .unwrap()
+                    .cb_sink
+                    .clone()
+                    .send(Box::new(append))
+                    .expect("append choice failed");
+            })
+            .button("Cancel", |s| {
+                s.pop_layer();
+            }),
+    );
+}
+fn output_file_name(siv: &mut Cursive) -> String {
+    siv.with_user_data(|user_data: &mut UserData| {
+        config::output_file(user_data.format).display().to_string()
+    })
+    .expect("No user data?")
+}
+fn save_gitignore(siv: &mut Cursive, save_mode: SaveMode) {
+    const MAX_LINES: usize = 10;
+    let Some((gitignore, warnings)) = get_gitignore(siv) else {
+        return;
+    };
+    let mut message = String::default();
+    if !warnings.is_empty() {
+        message += format!(
+            "Some rules can't be written exactly as {} rules:\n",
+            output_file_name(siv)
+        )
+        .as_str();
+        for warning in warnings.iter().take(MAX_LINES) {
+            message += format!("\n{}", warning).as_str();
+        }
+        if warnings.len() > MAX_LINES {
+            message += format!("\n...and {} more", warnings.len() - MAX_LINES).as_str();
+        }
+    }
+    let (format, path) = siv
+        .with_user_data(|user_data: &mut UserData| (user_data.format, user_data.output_path()))
+        .expect("No user data?");
+    if format == Format::Gitignore {
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let tracked_files = git::ignored_tracked_files(dir, &Gitignore::parse(&gitignore));
+        if !tracked_files.is_empty() {
+            if !message.is_empty() {
+                message += "\n\n";
+            }
+            message += "These tracked files would be ignored:\n";
+            for path in tracked_files.iter().take(MAX_LINES) {
+                message += format!("\n{}", path).as_str();
+            }
+            if tracked_files.len() > MAX_LINES {
+                message += format!("\n...and {} more", tracked_files.len() - MAX_LINES).as_str();
+            }
+        }
+    }
+    if message.is_empty() {
+        write_gitignore(siv, save_mode, &gitignore);
+    } else {
+        siv.add_layer(
+            Dialog::text(message)
+                .title("Warning")
+                .button("Save anyway", move |s| {
+                    s.pop_layer();
+                    write_gitignore(s, save_mode, &gitignore);
+                })
+                .button("Cancel", |s| {
+                    s.pop_layer();
+                }),
+        );
+    }
+}
+/// Fetches the selected templates and translates them into the output format.
+/// Returns the rules along with descriptions of the ones that couldn't be translated exactly.
+fn get_gitignore(siv: &mut Cursive) -> Option<(String, Vec<String>)> {
+    let (gitignore, format) = siv
+        .with_user_data(|user_data: &mut UserData| {
+            let selected_templates = user_data.templates.selected_template_names();
+            (
+                user_data.source.fetch(&selected_templates),
+                user_data.format,
+            )
+        })
+        .expect("No user data?");
+    match gitignore {
+        Ok(gitignore) => {
+            let gitignore = gig_gen::combine("", &gitignore);
+            let conversion = format::convert(&Gitignore::parse(&gitignore), format);
+            Some((conversion.text, conversion.warnings))
+        }
+        Err(error) => {
+            siv.add_layer(Dialog::info(error.to_string()).h_align(HAlign::Center));
+            None
+        }
+    }
+}
+fn write_gitignore(siv: &mut Cursive, save_mode: SaveMode, gitignore: &str) {
+    let file_name = output_file_name(siv);
+    let (format, path, template_names) = siv
+        .with_user_data(|user_data: &mut UserData| {
+            (
+                user_data.format,
+                user_data.output_path(),
+                user_data.templates.selected_template_names(),
+            )
+        })
+        .expect("No user data?");
+    let mut final_message = match save_mode {
+        SaveMode::Create => format!("Created new {} file.", file_name),
+        SaveMode::Overwrite => format!("Replaced contents of existing {} file.", file_name),
+        SaveMode::Append => format!("Appended templates to existing {} file.", file_name),
+        SaveMode::Update => format!("Updated templates in existing {} file.", file_name),
+    };
+    match gig_gen::save(&path, gitignore, format, save_mode) {
+        Ok(()) => {
+            // Record the templates the .gitignore file was generated from. The file has been
+            // saved, so the app closes even if they can't be recorded, and says why
+            if format == Format::Gitignore {
+                let lock_path = lock::path(&path);
+                let append = save_mode == SaveMode::Append;
+                let templates = siv
+                    .with_user_data(|user_data: &mut UserData| user_data.source.templates())
+                    .expect("No user data?");
+                let recorded = templates.and_then(|templates| {
+                    lock::record(&lock_path, &templates, &template_names, append)
+                });
+                if let Err(error) = recorded {
+                    final_message = format!(
+                        "{} The templates weren't recorded in {}: {}",
+                        final_message,
+                        lock_path.display(),
+                        error
+                    );
+                }
+            }
+            siv.with_user_data(|user_data: &mut UserData| {
+                user_data.final_message = Some(final_message);
+            });
+            siv.quit();
+        }
+        Err(error) => {
+            siv.add_layer(Dialog::info(error.to_string()).h_align(HAlign::Center));
+        }
+    }
+}
+fn overwrite(siv: &mut Cursive) {
+    save_gitignore(siv, SaveMode::Overwrite);
+}
+fn append(siv: &mut Cursive) {
+    save_gitignore(siv, SaveMode::Append);
+}
+fn update(siv: &mut Cursive) {
+    save_gitignore(siv, SaveMode::Update);
+}
+fn create(siv: &mut Cursive) {
+    save_gitignore(siv, SaveMode::Create);
+}
+fn nothing_to_save_warning(siv: &mut Cursive) {
+    siv.add_layer(
+        Dialog::info("Select one or more templates and try again.").h_align(HAlign::Center),
+    );
+}
+fn select_item(siv: &mut Cursive, selection: &str) {
+    siv.with_user_data(|user_data: &mut UserData| {
+        user_data.templates.select_template(selection);
+    });
+    refresh(siv);
+}
+fn unselect_item(siv: &mut Cursive, selection: &str) {
+    siv.with_user_data(|user_data: &mut UserData| {
+        user_data.templates.unselect_template(selection);
+    });
+    refresh(siv);
+}
+fn refresh(siv: &mut Cursive) {
+    let mut available_view = siv
+        .find_name::<SelectView<String>>(AVAILABLE_VIEW_NAME)
+        .unwrap();
+    let available_index = available_view.selected_id().unwrap_or_default();
+
+    let mut selected_view = siv.find_name::<SelectView>(SELECTED_VIEW_NAME).unwrap();
+
+    let mut filter_view = siv.find_name::<TextView>(FILTER_VIEW_NAME).unwrap();
+
+    let filter_mode = config::get().filter_mode.unwrap_or(FilterMode::Prefix);
+
+    siv.with_user_data(|user_data: &mut UserData| {
+        // Display the filter
+        filter_view.set_content(format!(" {}", user_data.filter));
+
+        // Display the possibly filtered list of available templates
+        available_view.clear();
+        user_data
+            .templates
+            .unselected_templates()
+            .iter()
+            .filter_map(|template| {
+                if !user_data.filter.is_empty()
+                    && !filter_mode.matches(template.name(), user_data.filter.as_str())
+                {
+                    None
+                } else {
+                    Some(template.name())
+                }
+            })
+            .for_each(|template_name| available_view.add_item_str(template_name));
+
+        // Set the selected item unless the list has just been filtered
+        if !user_data.new_filter {
+            available_view.set_selection(available_index);
+            user_data.new_filter = false;
+        }
+
+        // Display the list of selected templates
+        selected_view.clear();
+        user_data
+            .templates
+            .selected_templates()
+            .iter()
+            .for_each(|option| selected_view.add_item_str(option.name()));
+    });
+}
+fn help(siv: &mut Cursive) {
+    let message = "Use this app to create a .gitignore file for one or more operating systems, programming languages or IDEs, using templates from https://www.toptal.com/developers/gitignore/
+
+Select the templates to include in the file.
+- If the current directory already has a generated .gitignore file, the templates it was generated from are selected when the app starts. Otherwise the default templates from the configuration file are selected.
+- Use the up and down arrows to highlight a template.
+- Press Enter to select the highlighted template.
+- Type the start of the template's name to filter the list.
+
+Press Ctrl+S to write the .gitignore file to disk.
+- The .gitignore file will be written to the current directory, unless the configuration file gives another path.
+- If the .gitignore file already exists, you will be given the option of replacing it or appending to it, or of updating the part of it that was generated from templates.
+- The templates are recorded in a .gig-gen.lock file next to the .gitignore file.
+- Choose File > Save as... to write a .dockerignore, .npmignore, .prettierignore, .eslintignore or .hgignore file instead.
+
+Press Ctrl+Q to close the app without writing the .gitignore file.";
+    siv.add_layer(Dialog::info(message).h_align(HAlign::Center));
+}
+fn about(siv: &mut Cursive) {
+    let mut styled = StyledString::styled("+---------------+\n", BaseColor::Yellow.dark());
+    styled.append(StyledString::styled("|", BaseColor::Yellow.dark()));
+    styled.append(StyledString::plain(" g i g - g e n "));
+    styled.append(StyledString::styled("|\n", BaseColor::Yellow.dark()));
+    styled.append(StyledString::styled(
+        "+---------------+\n",
+        BaseColor::Yellow.dark(),
+    ));
+    styled.append(StyledString::plain(format!(
+        "v {}\n",
+        env!("CARGO_PKG_VERSION")
+    )));
+    styled.append(StyledString::plain("Copyright © 2024 Paul Sobolik\n\n"));
+    styled.append(StyledString::plain("API and templates provided by\n"));
+    styled.append(StyledString::plain(
+        "https://www.toptal.com/developers/gitignore/",
+    ));
+
+    siv.add_layer(
+        Dialog::around(TextView::new(styled).h_align(HAlign::Center))
+            .h_align(HAlign::Center)
+            .button("Ok", |s| {
+                s.pop_layer();
+            }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::{Receiver, Sender};
+    use cursive::backends::puppet::{self, observed::ObservedScreen};
+    use cursive::{CursiveRunner, Vec2};
+    use gig_gen::Error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const TEMPLATES: [(&str, &str); 4] = [
+        ("broken", ""),
+        ("macos", "\n### macOS ###\n.DS_Store\n*~\n"),
+        ("rust", "\n### Rust ###\n/target/\n**/*.rs.bk\n"),
+        ("vim", "\n### Vim ###\n*.swp\n*~\n"),
+    ];
+    /// The column the selected templates are shown in
+    const SELECTED_COLUMN: usize = 40;
+
+    /// Templates that don't come from the API. Fetching `broken` fails.
+    struct Fixture;
+
+    impl TemplateSource for Fixture {
+        fn names(&self) -> Result<Vec<String>> {
+            Ok(TEMPLATES.iter().map(|(name, _)| name.to_string()).collect())
+        }
+        fn fetch(&self, template_names: &[String]) -> Result<String> {
+            if template_names.iter().any(|name| name == "broken") {
+                return Err(Error::HttpStatus {
+                    url: "https://example.com/api/broken".to_string(),
+                    status: 500,
+                    reason: "Internal Server Error".to_string(),
+                });
+            }
+            Ok(generated(template_names))
+        }
+        fn templates(&self) -> Result<Vec<TemplateInfo>> {
+            Ok(TEMPLATES
+                .iter()
+                .map(|(name, contents)| TemplateInfo {
+                    key: name.to_string(),
+                    name: name.to_string(),
+                    contents: contents.to_string(),
+                    source: "fixture".to_string(),
+                    cached: false,
+                })
+                .collect())
+        }
+    }
+
+    /// Returns the file the API would generate from the named templates.
+    fn generated(template_names: &[String]) -> String {
+        let url = format!(
+            "https://www.toptal.com/developers/gitignore/api/{}",
+            template_names.join(",")
+        );
+        let mut text = format!("# Created by {}\n", url);
+        for name in template_names {
+            let (_, contents) = TEMPLATES.iter().find(|(key, _)| key == name).unwrap();
+            text += contents;
+        }
+        text + format!("\n# End of {}\n", url).as_str()
+    }
+
+    /// The app running on the puppet backend, in a temporary directory.
+    struct App {
+        runner: CursiveRunner<Cursive>,
+        input: Sender<Option<Event>>,
+        frames: Receiver<ObservedScreen>,
+        dir: PathBuf,
+    }
+
+    impl App {
+        /// Starts the app in a directory with `files` in it.
+        fn start(files: &[(&str, &str)]) -> Self {
+            static COUNT: AtomicUsize = AtomicUsize::new(0);
+            let dir = std::env::temp_dir().join(format!(
+                "gig-gen-tui-{}-{}",
+                std::process::id(),
+                COUNT.fetch_add(1, Ordering::Relaxed)
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            for (name, contents) in files {
+                std::fs::write(dir.join(name), contents).unwrap();
+            }
+            let backend = puppet::Backend::init(Some(Vec2::new(80, 24)));
+            let input = backend.input();
+            let frames = backend.stream();
+            let mut siv = Cursive::new();
+            setup(&mut siv, Box::new(Fixture), &dir);
+            let mut runner = siv.into_runner(backend);
+            runner.refresh();
+            App {
+                runner,
+                input,
+                frames,
+                dir,
+            }
+        }
+        fn press(&mut self, event: Event) {
+            self.input.send(Some(event)).unwrap();
+            self.runner.step();
+        }
+        fn type_text(&mut self, text: &str) {
+            text.chars().for_each(|c| self.press(Event::Char(c)));
+        }
+        /// Returns the text on the screen, one line for each row.
+        fn screen(&mut self) -> Vec<String> {
+            self.runner.refresh();
+            let screen = self.frames.try_iter().last().expect("Nothing was drawn");
+            (0..screen.size().y)
+                .map(|y| {
+                    let line: String = (0..screen.size().x)
+                        .filter_map(|x| match &screen[Vec2::new(x, y)] {
+                            Some(cell) => cell.letter.as_option().cloned(),
+                            None => Some(" ".to_string()),
+                        })
+                        .collect();
+                    line.trim_end().to_string()
+                })
+                .collect()
+        }
+        fn shows(&mut self, text: &str) -> bool {
+            self.screen().iter().any(|line| line.contains(text))
+        }
+        /// Returns the available templates and the selected templates on the screen.
+        fn lists(&mut self) -> (Vec<String>, Vec<String>) {
+            let mut available = Vec::new();
+            let mut selected = Vec::new();
+            let screen = self.screen();
+            // Below the menu bar and the labels, and above the filter
+            for line in &screen[2..screen.len() - 2] {
+                let (left, right) = line.split_at(SELECTED_COLUMN.min(line.len()));
+                if !left.trim().is_empty() {
+                    available.push(left.trim().to_string());
+                }
+                if !right.trim().is_empty() {
+                    selected.push(right.trim().to_string());
+                }
+            }
+            (available, selected)
+        }
+        fn filter(&mut self) -> String {
+            let screen = self.screen();
+            screen[screen.len() - 1].to_string()
+        }
+        fn read(&self, name: &str) -> Option<String> {
+            std::fs::read_to_string(self.dir.join(name)).ok()
+        }
+        fn final_message(&mut self) -> Option<String> {
+            self.runner
+                .with_user_data(|user_data: &mut UserData| user_data.final_message.clone())
+                .flatten()
+        }
+    }
+
+    impl Drop for App {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn starts_with_no_templates_selected() {
+        let mut app = App::start(&[]);
+        assert!(app.shows("Available templates"));
+        assert_eq!(
+            app.lists(),
+            (names(&["broken", "macos", "rust", "vim"]), vec![])
+        );
+        assert_eq!(app.filter(), "Filter:");
+    }
+
+    #[test]
+    fn starts_with_the_templates_in_the_gitignore_file() {
+        let gitignore = generated(&names(&["rust", "vim"]));
+        let mut app = App::start(&[(".gitignore", &gitignore)]);
+        assert_eq!(
+            app.lists(),
+            (names(&["broken", "macos"]), names(&["rust", "vim"]))
+        );
+    }
+
+    #[test]
+    fn typing_filters_the_templates() {
+        let mut app = App::start(&[]);
+        app.type_text("m");
+        assert_eq!(app.filter(), "Filter: m");
+        assert_eq!(app.lists().0, names(&["macos"]));
+        app.type_text("x");
+        assert_eq!(app.lists().0, Vec::<String>::new());
+        app.press(Event::Key(Key::Backspace));
+        assert_eq!(app.filter(), "Filter: m");
+        assert_eq!(app.lists().0, names(&["macos"]));
+        app.press(Event::Key(Key::Esc));
+        assert_eq!(app.filter(), "Filter:");
+        assert_eq!(app.lists().0, names(&["broken", "macos", "rust", "vim"]));
+    }
+
+    #[test]
+    fn filter_keys_are_not_shortcuts() {
+        let mut app = App::start(&[]);
+        app.type_text("qs");
+        assert!(app.runner.is_running());
+        assert_eq!(app.filter(), "Filter: qs");
+    }
+
+    #[test]
+    fn enter_selects_and_unselects_templates() {
+        let mut app = App::start(&[]);
+        app.type_text("v");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::Key(Key::Esc));
+        app.type_text("mac");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::Key(Key::Esc));
+        // The selected templates stay in the order of the list, not the order they were selected in
+        assert_eq!(
+            app.lists(),
+            (names(&["broken", "rust"]), names(&["macos", "vim"]))
+        );
+        app.press(Event::Key(Key::Right));
+        app.press(Event::Key(Key::Down));
+        app.press(Event::Key(Key::Enter));
+        assert_eq!(
+            app.lists(),
+            (names(&["broken", "rust", "vim"]), names(&["macos"]))
+        );
+    }
+
+    #[test]
+    fn saving_nothing_shows_a_warning() {
+        let mut app = App::start(&[]);
+        app.press(Event::CtrlChar('s'));
+        assert!(app.shows("Select one or more templates and try again."));
+        assert!(app.runner.is_running());
+        app.press(Event::Key(Key::Enter));
+        assert!(!app.shows("Select one or more templates"));
+        assert_eq!(app.read(".gitignore"), None);
+    }
+
+    #[test]
+    fn save_creates_the_file_and_lockfile() {
+        let mut app = App::start(&[]);
+        app.type_text("v");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::Key(Key::Esc));
+        app.type_text("m");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::CtrlChar('s'));
+        assert!(!app.runner.is_running());
+        assert_eq!(
+            app.final_message().as_deref(),
+            Some("Created new .gitignore file.")
+        );
+        let gitignore = app.read(".gitignore").unwrap();
+        assert!(gitignore.starts_with(
+            "# Created by https://www.toptal.com/developers/gitignore/api/macos,vim\n"
+        ));
+        assert!(gitignore.contains("\n### macOS ###\n.DS_Store\n*~\n"));
+        // The rule that's already in the macOS template is left out
+        assert_eq!(gitignore.matches("*~").count(), 1);
+        let lockfile = app.read(lock::FILE_NAME).unwrap();
+        assert!(lockfile.contains(r#"name = "macos""#));
+        assert!(lockfile.contains(r#"name = "vim""#));
+        assert!(!lockfile.contains(r#"name = "rust""#));
+    }
+
+    #[test]
+    fn save_says_if_the_lockfile_cant_be_written() {
+        let mut app = App::start(&[]);
+        std::fs::create_dir(app.dir.join(lock::FILE_NAME)).unwrap();
+        app.type_text("v");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::CtrlChar('s'));
+        assert!(!app.runner.is_running());
+        let final_message = app.final_message().unwrap();
+        assert!(
+            final_message
+                .starts_with("Created new .gitignore file. The templates weren't recorded in "),
+            "{}",
+            final_message
+        );
+        assert!(app.read(".gitignore").unwrap().contains("*.swp\n"));
+    }
+
+    #[test]
+    fn an_existing_file_can_be_overwritten() {
+        let mut app = App::start(&[(".gitignore", "node_modules/\n")]);
+        app.type_text("r");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::CtrlChar('s'));
+        assert!(app.shows("A .gitignore file already"));
+        assert!(app.shows("<Overwrite>"));
+        assert!(app.shows("<Append>"));
+        // There's no generated part to update
+        assert!(!app.shows("<Update>"));
+        app.press(Event::Key(Key::Enter));
+        assert!(!app.runner.is_running());
+        assert_eq!(
+            app.final_message().as_deref(),
+            Some("Replaced contents of existing .gitignore file.")
+        );
+        let gitignore = app.read(".gitignore").unwrap();
+        assert!(!gitignore.contains("node_modules/"));
+        assert!(gitignore.contains("/target/\n"));
+    }
+
+    #[test]
+    fn templates_can_be_appended_to_an_existing_file() {
+        let gitignore = generated(&names(&["macos"]));
+        let mut app = App::start(&[(".gitignore", &gitignore)]);
+        app.type_text("r");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::CtrlChar('s'));
+        // Update, Overwrite, Append
+        app.press(Event::Key(Key::Right));
+        app.press(Event::Key(Key::Right));
+        app.press(Event::Key(Key::Enter));
+        assert_eq!(
+            app.final_message().as_deref(),
+            Some("Appended templates to existing .gitignore file.")
+        );
+        let gitignore = app.read(".gitignore").unwrap();
+        assert!(gitignore.starts_with(&generated(&names(&["macos"]))));
+        assert!(gitignore.contains("/target/\n"));
+    }
+
+    #[test]
+    fn the_generated_part_of_a_file_can_be_updated() {
+        let gitignore = format!("# Local files\n.env\n\n{}", generated(&names(&["macos"])));
+        let mut app = App::start(&[(".gitignore", &gitignore)]);
+        app.type_text("r");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::CtrlChar('s'));
+        assert!(app.shows("<Update>"));
+        app.press(Event::Key(Key::Enter));
+        assert_eq!(
+            app.final_message().as_deref(),
+            Some("Updated templates in existing .gitignore file.")
+        );
+        let gitignore = app.read(".gitignore").unwrap();
+        assert!(gitignore.starts_with("# Local files\n.env\n\n"));
+        assert!(gitignore.contains("/api/macos,rust\n"));
+        assert!(gitignore.contains("/target/\n"));
+    }
+
+    #[test]
+    fn cancel_leaves_the_file_alone() {
+        let mut app = App::start(&[(".gitignore", "node_modules/\n")]);
+        app.type_text("r");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::CtrlChar('s'));
+        // Overwrite, Append, Cancel
+        app.press(Event::Key(Key::Right));
+        app.press(Event::Key(Key::Right));
+        app.press(Event::Key(Key::Enter));
+        assert!(app.runner.is_running());
+        assert!(!app.shows("already"));
+        assert_eq!(app.read(".gitignore").as_deref(), Some("node_modules/\n"));
+        assert_eq!(app.read(lock::FILE_NAME), None);
+    }
+
+    #[test]
+    fn a_template_that_cant_be_fetched_shows_the_error() {
+        let mut app = App::start(&[]);
+        app.type_text("b");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::CtrlChar('s'));
+        assert!(app.shows("500 Internal Server Error"));
+        assert!(app.runner.is_running());
+        assert_eq!(app.read(".gitignore"), None);
+    }
+
+    #[test]
+    fn ctrl_q_quits_without_saving() {
+        let mut app = App::start(&[]);
+        app.type_text("r");
+        app.press(Event::Key(Key::Enter));
+        app.press(Event::CtrlChar('q'));
+        assert!(!app.runner.is_running());
+        assert_eq!(app.final_message(), None);
+        assert_eq!(app.read(".gitignore"), None);
+    }
+}
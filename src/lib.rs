@@ -0,0 +1,97 @@
+//! Generate `.gitignore` files from the templates at
+//! [gitignore.io](https://www.toptal.com/developers/gitignore/).
+//!
+//! A [`Client`] lists and fetches the templates, and the functions at the top of the crate
+//! combine and save them:
+//!
+//! ```no_run
+//! use gig_gen::{Client, Format, SaveMode};
+//! use std::path::Path;
+//!
+//! let names = vec!["rust".to_string(), "vim".to_string()];
+//! let rules = Client::new()?.generate(&names)?;
+//! gig_gen::save(Path::new(".gitignore"), &rules, Format::Gitignore, SaveMode::Update)?;
+//! # Ok::<(), gig_gen::Error>(())
+//! ```
+
+mod audit;
+mod block;
+mod builtin;
+mod cache;
+mod client;
+mod config;
+mod diff;
+mod error;
+mod format;
+mod git;
+mod gitignore;
+mod gitignore_api;
+mod http;
+mod lint;
+mod lock;
+mod matcher;
+mod normalize;
+mod outdated;
+mod save;
+mod server;
+mod template;
+
+pub use client::Client;
+pub use error::{Error, Result};
+pub use format::Format;
+pub use gitignore_api::{find_template, parse_template_names, TemplateInfo};
+pub use http::ProxyEnvironment;
+pub use save::{save, saved_contents, SaveMode};
+pub use template::{Template, Templates};
+
+/// The parts the `gig-gen` program is built from, such as the `.gitignore` parser, linter
+/// and template server. They aren't part of the library's API, and can change in any version.
+#[doc(hidden)]
+pub mod internal {
+    pub mod audit {
+        pub use crate::audit::suggest_templates;
+    }
+    pub mod block {
+        pub use crate::block::{find_blocks, regenerate, replace, GeneratedBlock};
+    }
+    pub mod config {
+        pub use crate::config::{get, load, output_file, FilterMode};
+    }
+    pub mod diff {
+        pub use crate::diff::unified;
+    }
+    pub mod format {
+        pub use crate::format::convert;
+    }
+    pub mod git {
+        pub use crate::git::{ignored_tracked_files, is_work_tree, untracked_paths};
+    }
+    pub mod gitignore {
+        pub use crate::gitignore::Gitignore;
+    }
+    pub mod lint {
+        pub use crate::lint::{fix, lint, Finding};
+    }
+    pub mod lock {
+        pub use crate::lock::{hash, path, record, Lockfile, FILE_NAME};
+    }
+    pub mod matcher {
+        pub use crate::matcher::Matcher;
+    }
+    pub mod outdated {
+        pub use crate::outdated::{check, State};
+    }
+    pub mod server {
+        pub use crate::server::{read_templates, serve};
+    }
+}
+
+/// Returns the templates built into the program with the `builtin-templates` feature, ordered by name.
+pub fn builtin_templates() -> Result<Vec<TemplateInfo>> {
+    builtin::templates()
+}
+/// Returns the rules in `generated` that aren't already in effect in `existing`, or
+/// earlier in `generated`, with the comments and blank lines.
+pub fn combine(existing: &str, generated: &str) -> String {
+    normalize::dedupe(existing, generated)
+}
@@ -0,0 +1,407 @@
+use crate::gitignore::{Gitignore, Line, LineKind, Rule};
+use crate::matcher::Matcher;
+use crate::normalize;
+use std::fmt;
+
+/// A problem with a line of a `.gitignore` file.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Finding {
+    pub line: usize,
+    pub kind: Kind,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    /// The rule repeats an earlier rule.
+    Duplicate,
+    /// An earlier rule already ignores everything the rule matches.
+    Shadowed,
+    /// The negation can't re-include anything, because a parent directory is ignored.
+    DeadNegation,
+    /// The line ends with whitespace.
+    TrailingWhitespace,
+    /// Git can't use the pattern as intended.
+    InvalidPattern,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Kind::Duplicate => "duplicate",
+            Kind::Shadowed => "shadowed",
+            Kind::DeadNegation => "dead-negation",
+            Kind::TrailingWhitespace => "trailing-whitespace",
+            Kind::InvalidPattern => "invalid-pattern",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Returns the problems found in `gitignore`, in line order.
+pub fn lint(gitignore: &Gitignore) -> Vec<Finding> {
+    let matcher = Matcher::new(gitignore);
+    let mut findings = Vec::new();
+    let mut rules: Vec<Rule> = Vec::new();
+    for line in gitignore.lines() {
+        let mut add = |kind, message| {
+            findings.push(Finding {
+                line: line.number(),
+                kind,
+                message,
+            })
+        };
+        if let Some(message) = trailing_whitespace(line) {
+            add(Kind::TrailingWhitespace, message.to_string());
+        }
+        let rule = match line.kind() {
+            LineKind::Rule(rule) => rule,
+            LineKind::Blank if !line.text().trim().is_empty() => {
+                add(Kind::InvalidPattern, "the pattern is empty".to_string());
+                continue;
+            }
+            _ => continue,
+        };
+        if let Some(message) = rule.error() {
+            add(Kind::InvalidPattern, message.to_string());
+        } else if normalize::is_redundant(&rules, rule) {
+            add(Kind::Duplicate, format!("{} repeats an earlier rule", rule));
+        } else if let Some(number) = shadowing_line(&matcher.before(line), rule) {
+            add(
+                Kind::Shadowed,
+                format!("{} is already ignored by line {}", rule, number),
+            );
+        } else if let Some((parent, number)) = ignored_parent(&matcher, rule) {
+            add(
+                Kind::DeadNegation,
+                format!(
+                    "{} can't re-include anything, because line {} ignores its parent directory {}",
+                    rule, number, parent
+                ),
+            );
+        }
+        rules.push(rule.clone());
+    }
+    findings
+}
+
+/// Fixes the problems in `gitignore` that can be fixed without changing which paths it
+/// ignores, and returns the fixed text with the problems that were fixed.
+///
+/// Duplicate, shadowed and dead rules are removed, and whitespace git ignores is
+/// removed from the ends of lines. Rules are never reordered.
+pub fn fix(gitignore: &Gitignore) -> (String, Vec<Finding>) {
+    let findings: Vec<Finding> = lint(gitignore)
+        .into_iter()
+        .filter(|finding| finding.kind.is_fixable())
+        .collect();
+    let mut fixed_whitespace = Vec::new();
+    let mut lines = Vec::new();
+    for line in gitignore.lines() {
+        let line_findings: Vec<&Finding> = findings
+            .iter()
+            .filter(|finding| finding.line == line.number())
+            .collect();
+        if line_findings
+            .iter()
+            .any(|finding| finding.kind.is_removed_by_fix())
+        {
+            continue;
+        }
+        let mut text = line.text().to_string();
+        if line_findings
+            .iter()
+            .any(|finding| finding.kind == Kind::TrailingWhitespace)
+        {
+            let trimmed = trim_trailing_whitespace(line);
+            if trimmed != text {
+                fixed_whitespace.push(line.number());
+                text = trimmed;
+            }
+        }
+        lines.push(text);
+    }
+    let mut fixed = lines.join("\n");
+    if gitignore.to_string().ends_with('\n') && !lines.is_empty() {
+        fixed.push('\n');
+    }
+    let findings = findings
+        .into_iter()
+        .filter(|finding| {
+            finding.kind != Kind::TrailingWhitespace || fixed_whitespace.contains(&finding.line)
+        })
+        .collect();
+    (fixed, findings)
+}
+
+impl Kind {
+    fn is_fixable(&self) -> bool {
+        self.is_removed_by_fix() || *self == Kind::TrailingWhitespace
+    }
+    fn is_removed_by_fix(&self) -> bool {
+        matches!(self, Kind::Duplicate | Kind::Shadowed | Kind::DeadNegation)
+    }
+}
+
+/// Removes the whitespace at the end of a line that doesn't change its meaning.
+fn trim_trailing_whitespace(line: &Line) -> String {
+    let (text, line_end) = match line.text().strip_suffix('\r') {
+        Some(text) => (text, "\r"),
+        None => (line.text(), ""),
+    };
+    let trimmed = if line.rule().is_some() {
+        // Only spaces are insignificant in a rule, and an escaped space isn't
+        let trimmed = text.trim_end_matches(' ');
+        if trimmed.ends_with('\\') && trimmed.len() < text.len() {
+            &text[..trimmed.len() + 1]
+        } else {
+            trimmed
+        }
+    } else {
+        text.trim_end()
+    };
+    format!("{}{}", trimmed, line_end)
+}
+fn trailing_whitespace(line: &Line) -> Option<&'static str> {
+    let text = line.text().strip_suffix('\r').unwrap_or(line.text());
+    if !text.ends_with([' ', '\t']) {
+        None
+    } else if line.rule().is_none() {
+        Some("the line ends with whitespace")
+    } else if text.ends_with('\t') {
+        Some("the line ends with a tab, which is part of the pattern")
+    } else if text.ends_with("\\ ") {
+        None
+    } else {
+        Some("git ignores trailing spaces unless they're escaped with a backslash")
+    }
+}
+/// Returns the number of the earlier line that already ignores everything `rule` matches,
+/// if `rule` is a pattern without wildcards.
+///
+/// `matcher` has the rules that come before `rule`.
+fn shadowing_line(matcher: &Matcher, rule: &Rule) -> Option<usize> {
+    if rule.is_negated() || !is_literal(rule.pattern()) {
+        return None;
+    }
+    let is_dir = rule.is_directory_only();
+    if rule.is_anchored() {
+        // The rule only matches one path, so the earlier rules shadow it if they ignore that path
+        let matched = matcher.explain(rule.pattern(), true)?;
+        if matched.is_ignored() && (is_dir || matcher.is_ignored(rule.pattern(), false)) {
+            return Some(matched.line().number());
+        }
+    } else {
+        // The rule matches the name in any directory, so only a rule that does too can shadow it
+        let matched = matcher.explain(rule.pattern(), is_dir)?;
+        let line = matched.line();
+        let matching_rule = line.rule()?;
+        let later_negation = matcher.after(line).any(|(_, rule)| rule.is_negated());
+        if matched.is_ignored() && !matching_rule.is_anchored() && !later_negation {
+            return Some(line.number());
+        }
+    }
+    None
+}
+/// Returns the parent directory of the paths a negated `rule` matches, and the number of
+/// the line that ignores it, if one is ignored.
+fn ignored_parent(matcher: &Matcher, rule: &Rule) -> Option<(String, usize)> {
+    if !rule.is_negated() || !rule.is_anchored() {
+        return None;
+    }
+    let segments: Vec<&str> = rule.pattern().split('/').collect();
+    let mut parent = String::new();
+    for segment in &segments[..segments.len() - 1] {
+        if !is_literal(segment) {
+            break;
+        }
+        if !parent.is_empty() {
+            parent.push('/');
+        }
+        parent.push_str(segment);
+        if let Some(matched) = matcher.explain(&parent, true) {
+            if matched.is_ignored() {
+                return Some((matched.path().to_string(), matched.line().number()));
+            }
+        }
+    }
+    None
+}
+fn is_literal(pattern: &str) -> bool {
+    !pattern.contains(['*', '?', '[', '\\'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the line number and kind of each finding in `text`.
+    fn findings(text: &str) -> Vec<(usize, Kind)> {
+        lint(&Gitignore::parse(text))
+            .into_iter()
+            .map(|finding| (finding.line, finding.kind))
+            .collect()
+    }
+    /// Fails if `fix` changes whether any of the paths are ignored.
+    fn assert_same_paths_ignored(text: &str) {
+        const PATHS: [&str; 14] = [
+            "target",
+            "target/debug",
+            "build",
+            "src/build",
+            "debug.log",
+            "important.log",
+            "logs",
+            "logs/keep.log",
+            "logs/other.log",
+            "node_modules",
+            "web/node_modules/x",
+            "foo",
+            "foo ",
+            "docs/api",
+        ];
+        let before = Gitignore::parse(text);
+        let (fixed, _) = fix(&before);
+        let after = Gitignore::parse(&fixed);
+        let (before, after) = (Matcher::new(&before), Matcher::new(&after));
+        for path in PATHS {
+            for is_dir in [false, true] {
+                assert_eq!(
+                    before.is_ignored(path, is_dir),
+                    after.is_ignored(path, is_dir),
+                    "{:?} (is_dir: {}) after fixing {:?}",
+                    path,
+                    is_dir,
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn no_findings() {
+        assert_eq!(findings("# Rust\ntarget/\n\n*.log\n!important.log\n"), []);
+    }
+
+    #[test]
+    fn duplicate() {
+        assert_eq!(
+            findings("target/\nnode_modules\ntarget/\n"),
+            [(3, Kind::Duplicate)]
+        );
+        assert_eq!(findings("!a\n!a\n"), [(2, Kind::Duplicate)]);
+    }
+
+    #[test]
+    fn repeat_after_a_negation_is_not_a_duplicate() {
+        // The third line ignores important.log again
+        assert_eq!(findings("*.log\n!important.log\n*.log\n"), []);
+    }
+
+    #[test]
+    fn shadowed() {
+        assert_eq!(findings("*.log\ndebug.log\n"), [(2, Kind::Shadowed)]);
+        assert_eq!(findings("build/\n/build/\n"), [(2, Kind::Shadowed)]);
+        assert_eq!(findings("docs/\ndocs/api\n"), [(2, Kind::Shadowed)]);
+        // An anchored rule doesn't ignore the name in other directories
+        assert_eq!(findings("/build\nbuild\n"), []);
+        // The negation in between re-includes the path, so the rule ignores it again
+        assert_eq!(findings("*.log\n!debug.log\ndebug.log\n"), []);
+        // A later negation doesn't make the rule needed
+        assert_eq!(
+            findings("*.log\ndebug.log\n!debug.log\n"),
+            [(2, Kind::Shadowed)]
+        );
+        // A rule for files only doesn't shadow a directory
+        assert_eq!(findings("/build\n/build/\n"), [(2, Kind::Shadowed)]);
+        assert_eq!(findings("/build/\n/build\n"), []);
+    }
+
+    #[test]
+    fn dead_negation() {
+        assert_eq!(
+            findings("logs/\n!logs/keep.log\n"),
+            [(2, Kind::DeadNegation)]
+        );
+        assert_eq!(findings("logs/*\n!logs/keep.log\n"), []);
+        let finding = &lint(&Gitignore::parse("logs/\n!logs/keep.log\n"))[0];
+        assert!(finding.message.contains("line 1"));
+    }
+
+    #[test]
+    fn trailing_whitespace() {
+        assert_eq!(
+            findings("foo  \n# comment \nbar\\ \nbaz\t\n\t\n"),
+            [
+                (1, Kind::TrailingWhitespace),
+                (2, Kind::TrailingWhitespace),
+                (4, Kind::TrailingWhitespace),
+                (5, Kind::TrailingWhitespace),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        assert_eq!(
+            findings("[abc\nfoo\\\n!\n/\n"),
+            [
+                (1, Kind::InvalidPattern),
+                (2, Kind::InvalidPattern),
+                (3, Kind::InvalidPattern),
+                (4, Kind::InvalidPattern),
+            ]
+        );
+    }
+
+    #[test]
+    fn fix_removes_redundant_rules_and_whitespace() {
+        let text = "target/\n# Logs  \n*.log\ndebug.log\nfoo\\ \nbar  \r\ntarget/\nlogs/\n!logs/keep.log\n[abc\n";
+        let (fixed, fixed_findings) = fix(&Gitignore::parse(text));
+        assert_eq!(
+            fixed,
+            "target/\n# Logs\n*.log\nfoo\\ \nbar\r\nlogs/\n[abc\n"
+        );
+        let fixed_findings: Vec<_> = fixed_findings
+            .iter()
+            .map(|finding| (finding.line, finding.kind))
+            .collect();
+        assert_eq!(
+            fixed_findings,
+            [
+                (2, Kind::TrailingWhitespace),
+                (4, Kind::Shadowed),
+                (6, Kind::TrailingWhitespace),
+                (7, Kind::Duplicate),
+                (9, Kind::DeadNegation),
+            ]
+        );
+    }
+
+    #[test]
+    fn fix_leaves_a_tab_in_a_pattern() {
+        let (fixed, fixed_findings) = fix(&Gitignore::parse("foo\t\n"));
+        assert_eq!(fixed, "foo\t\n");
+        assert!(fixed_findings.is_empty());
+    }
+
+    #[test]
+    fn fix_does_not_change_what_is_ignored() {
+        for text in [
+            "*.log\n!important.log\n*.log\n",
+            "*.log\ndebug.log\n!debug.log\n",
+            "*.log\n!debug.log\ndebug.log\n",
+            "target/\nnode_modules\ntarget/\nnode_modules/\n",
+            "build/\n/build/\n/build\nbuild\n",
+            "/build\nbuild\n/build/\n",
+            "logs/\n!logs/keep.log\nlogs/other.log\n",
+            "logs/*\n!logs/keep.log\nlogs/other.log\n",
+            "docs/\ndocs/api\n!docs/api\n",
+            "foo  \nfoo\\ \nfoo\n",
+            "!important.log\n*.log\n!important.log\n!important.log\n",
+        ] {
+            assert_same_paths_ignored(text);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::error::{Error, Result};
+use crate::gitignore_api::{self, TemplateInfo};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// The name of the file that records the templates a `.gitignore` file was generated from.
+pub const FILE_NAME: &str = ".gig-gen.lock";
+
+const HEADER: &str =
+    "# Written by gig-gen. It records the templates the .gitignore file was generated from.\n";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Lockfile {
+    #[serde(rename = "template", default)]
+    pub templates: Vec<LockedTemplate>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LockedTemplate {
+    pub name: String,
+    /// The URL of the API the template came from
+    pub source: String,
+    /// The hash of the template's contents, as `sha256:<hex digits>`
+    pub hash: String,
+}
+
+impl LockedTemplate {
+    pub fn new(template: &TemplateInfo) -> Self {
+        Self {
+            name: template.key.clone(),
+            source: template.source.clone(),
+            hash: hash(&template.contents),
+        }
+    }
+}
+
+impl Lockfile {
+    /// Reads a lockfile. Returns `None` if there isn't one.
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(Error::io(path, error)),
+        };
+        match toml::from_str(&contents) {
+            Ok(lockfile) => Ok(Some(lockfile)),
+            Err(error) => Err(Error::parse(path.display(), error)),
+        }
+    }
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let contents = toml::to_string(self).expect("A lockfile can always be serialized");
+        std::fs::write(path, format!("{}\n{}", HEADER, contents))
+            .map_err(|error| Error::io(path, error))
+    }
+    /// Returns the locked template named `name`, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&LockedTemplate> {
+        self.templates
+            .iter()
+            .find(|template| template.name.eq_ignore_ascii_case(name))
+    }
+    /// Records `templates`, replacing the ones that are already recorded.
+    pub fn update<'a>(&mut self, templates: impl IntoIterator<Item = &'a TemplateInfo>) {
+        for template in templates {
+            let locked = LockedTemplate::new(template);
+            match self
+                .templates
+                .iter_mut()
+                .find(|existing| existing.name.eq_ignore_ascii_case(&locked.name))
+            {
+                Some(existing) => *existing = locked,
+                None => self.templates.push(locked),
+            }
+        }
+    }
+}
+
+/// Records the named templates from `templates` in the lockfile at `path`. If `append` is true,
+/// the templates are added to the ones that are already recorded.
+pub fn record(
+    path: &Path,
+    templates: &[TemplateInfo],
+    template_names: &[String],
+    append: bool,
+) -> Result<()> {
+    let mut lockfile = Lockfile::default();
+    if append {
+        if let Ok(Some(existing)) = Lockfile::read(path) {
+            lockfile = existing;
+        }
+    }
+    lockfile.update(
+        template_names
+            .iter()
+            .filter_map(|name| gitignore_api::find_template(templates, name)),
+    );
+    lockfile.write(path)
+}
+/// Returns the path of the lockfile for the `.gitignore` file at `output`.
+pub fn path(output: &Path) -> PathBuf {
+    match output.parent() {
+        Some(parent) => parent.join(FILE_NAME),
+        None => PathBuf::from(FILE_NAME),
+    }
+}
+pub fn hash(contents: &str) -> String {
+    let digest = Sha256::digest(contents.as_bytes());
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256:{}", hex)
+}
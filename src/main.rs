@@ -1,68 +1,70 @@
 mod args;
-mod gitignore_api;
-mod template;
+mod interactive;
+mod output;
 
-use crate::template::Templates;
-use args::{Args, Commands, FilterArgs};
-use cursive::{
-    align::HAlign,
-    event::{Event, EventResult, Key},
-    menu,
-    style::{
-        BaseColor, BorderStyle, Color, ColorStyle, Effect, Palette, PaletteColor, PaletteStyle,
-        Style,
-    },
-    theme::Theme,
-    traits::*,
-    utils::markup::StyledString,
-    views::{Dialog, DummyView, LinearLayout, OnEventView, SelectView, TextView},
-    Cursive,
+use args::{
+    Args, Commands, FileFormat, FilterArgs, LintArgs, OutdatedArgs, OutputFormat, PathArgs,
+    ServeArgs, Source, TemplateArgs, UpgradeArgs,
 };
-use std::fs::OpenOptions;
+use gig_gen::internal::block::{self, GeneratedBlock};
+use gig_gen::internal::config::{self, FilterMode};
+use gig_gen::internal::format;
+use gig_gen::internal::gitignore::Gitignore;
+use gig_gen::internal::lock::{self, Lockfile};
+use gig_gen::internal::matcher::Matcher;
+use gig_gen::internal::{audit, diff, git, lint, outdated, server};
+use gig_gen::{Client, Error, Format, Result, SaveMode};
 use std::io::Write;
-use std::path::Path;
+use std::net::TcpListener;
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
 
-const AVAILABLE_VIEW_NAME: &str = "available";
-const SELECTED_VIEW_NAME: &str = "selected";
-const FILTER_VIEW_NAME: &str = "filter";
 const OUTPUT_FILE_NAME: &str = ".gitignore";
 
-type CbSink = crossbeam_channel::Sender<Box<dyn FnOnce(&mut Cursive) + Send>>;
-
-enum SaveOption {
-    Create,
-    Overwrite,
-    Append,
-}
-
-struct UserData {
-    templates: Templates,
-    filter: String,
-    new_filter: bool,
-    cb_sink: CbSink,
-    final_message: Option<String>,
-}
-
-fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
+fn main() -> ExitCode {
     let args: Args = clap::Parser::parse();
-    match args.command.unwrap_or(Commands::Interactive) {
-        Commands::List(args) => list_templates(args),
-        Commands::Generate(args) => generate_gitignore(args.templates),
-        Commands::Interactive => {
-            interactive();
-            Ok(())
+    match run(args) {
+        Ok(exit_code) => exit_code,
+        Err(error) => {
+            print_message(error.to_string().as_str());
+            ExitCode::from(error.exit_code())
         }
     }
 }
-fn list_templates(args: FilterArgs) -> Result<(), Box<dyn std::error::Error + 'static>> {
-    let mut templates = gitignore_api::get_template_names()?;
+/// Runs the command. Returns the status to exit with when it doesn't fail.
+fn run(args: Args) -> Result<ExitCode> {
+    config::load()?;
+    let client = Client::new()?.refresh_cache(args.refresh);
+    let result = match args.command.unwrap_or(Commands::Interactive) {
+        Commands::List(args) => list_templates(&client, args),
+        Commands::Generate(args) => generate_gitignore(&client, args),
+        Commands::Interactive => interactive(client),
+        Commands::Explain(args) => explain(args),
+        Commands::Audit => audit(&client),
+        Commands::Lint(args) => return lint(args),
+        Commands::Outdated(args) => return outdated(&client, args),
+        Commands::Upgrade(args) => upgrade(&client, args),
+        Commands::Serve(args) => serve(&client, args),
+    };
+    result.map(|()| ExitCode::SUCCESS)
+}
+fn list_templates(client: &Client, args: FilterArgs) -> Result<()> {
+    if args.format != OutputFormat::Plain {
+        return list_template_details(client, args);
+    }
+    let mut templates = match args.source {
+        Source::Api => client.list_templates()?,
+        Source::Builtin => gig_gen::builtin_templates()?
+            .into_iter()
+            .map(|template| template.key)
+            .collect(),
+    };
     if let Some(filter) = args.filter {
-        let filter = regex::escape(filter.as_str());
-        let re = regex::Regex::new(filter.as_str())?;
+        let filter_mode = config::get().filter_mode.unwrap_or(FilterMode::Contains);
         templates = templates
             .iter()
             .filter_map(|t| {
-                if re.is_match(t) {
+                if filter_mode.matches(t, &filter) {
                     Some(t.to_string())
                 } else {
                     None
@@ -79,497 +81,675 @@ fn list_templates(args: FilterArgs) -> Result<(), Box<dyn std::error::Error + 's
     }
     Ok(())
 }
-fn generate_gitignore(
-    template_names: Vec<String>,
-) -> Result<(), Box<dyn std::error::Error + 'static>> {
-    match gitignore_api::get_template(&template_names) {
-        Ok(result) => {
-            println!("{}", result);
-            Ok(())
-        }
-        Err(error) => {
-            let message = format!(
-                r#"Problem getting .gitignore for "{}": "#,
-                template_names.join(" ")
-            );
-            print_message(message.as_str());
-            Err(error.into())
+fn list_template_details(client: &Client, args: FilterArgs) -> Result<()> {
+    #[derive(serde::Serialize)]
+    struct Entry<'a> {
+        name: &'a str,
+        display_name: &'a str,
+        source: &'a str,
+        cached: bool,
+    }
+    let mut templates = match args.source {
+        Source::Api => client.templates()?,
+        Source::Builtin => gig_gen::builtin_templates()?,
+    };
+    if let Some(filter) = args.filter {
+        let filter_mode = config::get().filter_mode.unwrap_or(FilterMode::Contains);
+        templates.retain(|template| filter_mode.matches(&template.key, &filter));
+        if templates.is_empty() {
+            print_message(format!(r#"No templates match "{}""#, filter).as_str());
         }
     }
+    let entries: Vec<Entry> = templates
+        .iter()
+        .map(|template| Entry {
+            name: &template.key,
+            display_name: &template.name,
+            source: &template.source,
+            cached: template.cached,
+        })
+        .collect();
+    if args.format == OutputFormat::Csv {
+        let rows: Vec<Vec<String>> = entries
+            .iter()
+            .map(|entry| {
+                vec![
+                    entry.name.to_string(),
+                    entry.display_name.to_string(),
+                    entry.source.to_string(),
+                    entry.cached.to_string(),
+                ]
+            })
+            .collect();
+        println!(
+            "{}",
+            output::csv(&["name", "display_name", "source", "cached"], &rows)
+        );
+    } else {
+        println!("{}", output::serialize(args.format, &entries));
+    }
+    Ok(())
 }
-fn interactive() {
-    fn load_templates() -> Templates {
-        let mut templates = Templates::new();
-        if let Ok(template_names) = gitignore_api::get_template_names() {
-            templates.set_list(template_names);
-        };
-        templates
-        // TODO: Handle error?
-    }
-    let mut siv = cursive::default();
-    siv.add_global_callback(Event::CtrlChar('q'), |siv| siv.quit());
-    siv.add_global_callback(Event::CtrlChar('s'), save);
-    siv.add_global_callback(Event::Key(Key::F1), help);
-    let user_data = UserData {
-        templates: load_templates(),
-        filter: String::default(),
-        new_filter: false,
-        cb_sink: siv.cb_sink().clone(),
-        final_message: None,
+fn generate_gitignore(client: &Client, mut args: TemplateArgs) -> Result<()> {
+    // Only a path given in the arguments is overwritten. The configured file keeps the
+    // rules that weren't generated from templates
+    let mode = match args.output {
+        Some(_) => SaveMode::Overwrite,
+        None => SaveMode::Update,
     };
-    siv.set_user_data(user_data);
-    siv.set_theme(theme());
-
-    siv.menubar()
-        .add_subtree(
-            "File",
-            menu::Tree::new().with(|tree| {
-                tree.add_leaf("Save ^S", save);
-                tree.add_leaf("Quit ^Q", Cursive::quit);
-            }),
-        )
-        .add_subtree(
-            "Help",
-            menu::Tree::new().with(|tree| {
-                tree.add_leaf("Help F1", help);
-                tree.add_leaf("About", about);
-            }),
+    args.output = output_path(&args);
+    let format = Format::from(args.format);
+    let lock_path = lock::path(
+        args.output
+            .as_deref()
+            .unwrap_or(Path::new(OUTPUT_FILE_NAME)),
+    );
+    let lockfile = if args.locked {
+        match Lockfile::read(&lock_path) {
+            Ok(Some(lockfile)) => Some(lockfile),
+            Ok(None) => {
+                return Err(Error::Invalid(format!(
+                    "There is no {} file",
+                    lock_path.display()
+                )))
+            }
+            Err(error) => return Err(error),
+        }
+    } else {
+        None
+    };
+    args.templates = template_names(&args, lockfile.as_ref())?;
+    if let Some(lockfile) = &lockfile {
+        check_locked(client, lockfile, &args.templates)?;
+    }
+    if args.output_format != OutputFormat::Plain {
+        return generate_template_rules(client, args);
+    }
+    let gitignore = client.generate(&args.templates)?;
+    let conversion = format::convert(&Gitignore::parse(&gitignore), format);
+    if !conversion.warnings.is_empty() {
+        print_message(
+            format!(
+                "Warning: some rules can't be written exactly as {} rules:",
+                format.file_name()
+            )
+            .as_str(),
         );
-    siv.set_autohide_menu(false);
-
-    siv.add_fullscreen_layer(event_view(main_layer()));
-    refresh(&mut siv);
-    siv.run();
-    siv.with_user_data(|user_data: &mut UserData| {
-        if let Some(final_message) = &user_data.final_message {
-            print_message(final_message);
+        for warning in &conversion.warnings {
+            eprintln!("  {}", warning);
         }
-    });
-}
-fn print_message(message: &str) {
-    eprintln!("[{}] \x1b[93m{}\x1b[0m", env!["CARGO_PKG_NAME"], message);
-}
-fn theme() -> Theme {
-    Theme {
-        shadow: false,
-        borders: BorderStyle::Simple,
-        palette: Palette::retro().with(|palette| {
-            palette[PaletteColor::Background] = Color::TerminalDefault;
-            palette[PaletteColor::View] = BaseColor::Black.dark();
-            palette[PaletteColor::Primary] = BaseColor::White.light();
-            palette[PaletteColor::Secondary] = BaseColor::Blue.light();
-            palette[PaletteColor::Tertiary] = BaseColor::Yellow.light();
-            palette[PaletteColor::Highlight] = BaseColor::Blue.dark();
-            palette[PaletteColor::HighlightText] = BaseColor::White.light();
-            palette[PaletteStyle::TitlePrimary] =
-                Style::from(BaseColor::Yellow.light()).combine(Effect::Bold);
-            palette[PaletteStyle::HighlightInactive] = Style::from(ColorStyle::new(
-                BaseColor::White.light(),
-                BaseColor::Black.light(),
-            ));
-        }),
     }
-}
-fn event_view(content: impl View) -> impl View {
-    fn clear_filter(siv: &mut Cursive) {
-        if let Some(user_data) = siv.user_data::<UserData>() {
-            user_data.filter = String::default();
-            user_data.new_filter = true;
+    match args.output {
+        Some(output) => {
+            write_output(&output, &conversion.text, format, mode)?;
+            if format == Format::Gitignore && !args.locked {
+                lock::record(&lock_path, &client.templates()?, &args.templates, false)?;
+            }
         }
-        refresh(siv);
+        None => println!("{}", conversion.text),
     }
-    fn backspace(siv: &mut Cursive) {
-        if let Some(user_data) = siv.user_data::<UserData>() {
-            if !user_data.filter.is_empty() {
-                user_data.filter = user_data.filter[..user_data.filter.len() - 1].to_string();
-                user_data.new_filter = true;
+    Ok(())
+}
+/// Returns the templates in the profile followed by the templates in the arguments.
+/// If there aren't any, returns the templates in the lockfile if it's given, or the
+/// default templates from the configuration file.
+fn template_names(args: &TemplateArgs, lockfile: Option<&Lockfile>) -> Result<Vec<String>> {
+    let config = config::get();
+    let mut templates = Vec::new();
+    if let Some(name) = &args.profile {
+        match config.profile(name) {
+            Some(profile) => templates.extend_from_slice(profile),
+            None => {
+                return Err(Error::Invalid(format!(
+                    r#"There is no profile named "{}""#,
+                    name
+                )))
             }
         }
-        refresh(siv);
     }
-    fn handle_char(siv: &mut Cursive, c: char) {
-        if let Some(user_data) = siv.user_data::<UserData>() {
-            user_data.filter += c.to_string().as_str();
-            user_data.new_filter = true;
+    for template in &args.templates {
+        if !templates.contains(template) {
+            templates.push(template.clone());
         }
-        refresh(siv);
-    }
-    OnEventView::new(content)
-        .on_event(Event::Key(Key::Esc), clear_filter)
-        .on_event(Event::Key(Key::Backspace), backspace)
-        .on_event(Event::Char('a'), |s| handle_char(s, 'a'))
-        .on_event(Event::Char('b'), |s| handle_char(s, 'b'))
-        .on_event(Event::Char('c'), |s| handle_char(s, 'c'))
-        .on_event(Event::Char('d'), |s| handle_char(s, 'd'))
-        .on_event(Event::Char('e'), |s| handle_char(s, 'e'))
-        .on_event(Event::Char('f'), |s| handle_char(s, 'f'))
-        .on_event(Event::Char('g'), |s| handle_char(s, 'g'))
-        .on_event(Event::Char('h'), |s| handle_char(s, 'h'))
-        .on_event(Event::Char('i'), |s| handle_char(s, 'i'))
-        .on_event(Event::Char('j'), |s| handle_char(s, 'j'))
-        .on_event(Event::Char('k'), |s| handle_char(s, 'k'))
-        .on_event(Event::Char('l'), |s| handle_char(s, 'l'))
-        .on_event(Event::Char('m'), |s| handle_char(s, 'm'))
-        .on_event(Event::Char('n'), |s| handle_char(s, 'n'))
-        .on_event(Event::Char('o'), |s| handle_char(s, 'o'))
-        .on_event(Event::Char('p'), |s| handle_char(s, 'p'))
-        .on_event(Event::Char('q'), |s| handle_char(s, 'q'))
-        .on_event(Event::Char('r'), |s| handle_char(s, 'r'))
-        .on_event(Event::Char('s'), |s| handle_char(s, 's'))
-        .on_event(Event::Char('t'), |s| handle_char(s, 't'))
-        .on_event(Event::Char('u'), |s| handle_char(s, 'u'))
-        .on_event(Event::Char('v'), |s| handle_char(s, 'v'))
-        .on_event(Event::Char('w'), |s| handle_char(s, 'w'))
-        .on_event(Event::Char('x'), |s| handle_char(s, 'x'))
-        .on_event(Event::Char('y'), |s| handle_char(s, 'y'))
-        .on_event(Event::Char('z'), |s| handle_char(s, 'z'))
-        .on_event(Event::Char('A'), |s| handle_char(s, 'A'))
-        .on_event(Event::Char('B'), |s| handle_char(s, 'B'))
-        .on_event(Event::Char('C'), |s| handle_char(s, 'C'))
-        .on_event(Event::Char('D'), |s| handle_char(s, 'D'))
-        .on_event(Event::Char('E'), |s| handle_char(s, 'E'))
-        .on_event(Event::Char('F'), |s| handle_char(s, 'F'))
-        .on_event(Event::Char('G'), |s| handle_char(s, 'G'))
-        .on_event(Event::Char('H'), |s| handle_char(s, 'H'))
-        .on_event(Event::Char('I'), |s| handle_char(s, 'I'))
-        .on_event(Event::Char('J'), |s| handle_char(s, 'J'))
-        .on_event(Event::Char('K'), |s| handle_char(s, 'K'))
-        .on_event(Event::Char('L'), |s| handle_char(s, 'L'))
-        .on_event(Event::Char('M'), |s| handle_char(s, 'M'))
-        .on_event(Event::Char('N'), |s| handle_char(s, 'N'))
-        .on_event(Event::Char('O'), |s| handle_char(s, 'O'))
-        .on_event(Event::Char('P'), |s| handle_char(s, 'P'))
-        .on_event(Event::Char('Q'), |s| handle_char(s, 'Q'))
-        .on_event(Event::Char('R'), |s| handle_char(s, 'R'))
-        .on_event(Event::Char('S'), |s| handle_char(s, 'S'))
-        .on_event(Event::Char('T'), |s| handle_char(s, 'T'))
-        .on_event(Event::Char('U'), |s| handle_char(s, 'U'))
-        .on_event(Event::Char('V'), |s| handle_char(s, 'V'))
-        .on_event(Event::Char('W'), |s| handle_char(s, 'W'))
-        .on_event(Event::Char('X'), |s| handle_char(s, 'X'))
-        .on_event(Event::Char('Y'), |s| handle_char(s, 'Y'))
-        .on_event(Event::Char('Z'), |s| handle_char(s, 'Z'))
-        .on_event(Event::Char('0'), |s| handle_char(s, '0'))
-        .on_event(Event::Char('1'), |s| handle_char(s, '1'))
-        .on_event(Event::Char('2'), |s| handle_char(s, '2'))
-        .on_event(Event::Char('3'), |s| handle_char(s, '3'))
-        .on_event(Event::Char('4'), |s| handle_char(s, '4'))
-        .on_event(Event::Char('5'), |s| handle_char(s, '5'))
-        .on_event(Event::Char('6'), |s| handle_char(s, '6'))
-        .on_event(Event::Char('7'), |s| handle_char(s, '7'))
-        .on_event(Event::Char('8'), |s| handle_char(s, '8'))
-        .on_event(Event::Char('9'), |s| handle_char(s, '9'))
-        .on_event(Event::Char(' '), |s| handle_char(s, ' '))
-        .on_event(Event::Char('!'), |s| handle_char(s, '!'))
-        .on_event(Event::Char('"'), |s| handle_char(s, '"'))
-        .on_event(Event::Char('#'), |s| handle_char(s, '#'))
-        .on_event(Event::Char('$'), |s| handle_char(s, '$'))
-        .on_event(Event::Char('%'), |s| handle_char(s, '%'))
-        .on_event(Event::Char('\''), |s| handle_char(s, '\''))
-        .on_event(Event::Char('('), |s| handle_char(s, '('))
-        .on_event(Event::Char(')'), |s| handle_char(s, ')'))
-        .on_event(Event::Char('*'), |s| handle_char(s, '*'))
-        .on_event(Event::Char('+'), |s| handle_char(s, '+'))
-        .on_event(Event::Char(','), |s| handle_char(s, ','))
-        .on_event(Event::Char('-'), |s| handle_char(s, '-'))
-        .on_event(Event::Char('.'), |s| handle_char(s, '.'))
-        .on_event(Event::Char('/'), |s| handle_char(s, '/'))
-        .on_event(Event::Char(':'), |s| handle_char(s, ':'))
-        .on_event(Event::Char(';'), |s| handle_char(s, ';'))
-        .on_event(Event::Char('<'), |s| handle_char(s, '<'))
-        .on_event(Event::Char('='), |s| handle_char(s, '='))
-        .on_event(Event::Char('>'), |s| handle_char(s, '>'))
-        .on_event(Event::Char('?'), |s| handle_char(s, '?'))
-        .on_event(Event::Char('\''), |s| handle_char(s, '\''))
-        .on_event(Event::Char('['), |s| handle_char(s, '['))
-        .on_event(Event::Char('\\'), |s| handle_char(s, '\\'))
-        .on_event(Event::Char(']'), |s| handle_char(s, ']'))
-        .on_event(Event::Char('^'), |s| handle_char(s, '^'))
-        .on_event(Event::Char('_'), |s| handle_char(s, '_'))
-        .on_event(Event::Char('`'), |s| handle_char(s, '`'))
-        .on_event(Event::Char('{'), |s| handle_char(s, '{'))
-        .on_event(Event::Char('|'), |s| handle_char(s, '|'))
-        .on_event(Event::Char('}'), |s| handle_char(s, '}'))
-        .on_event(Event::Char('~'), |s| handle_char(s, '~'))
+    }
+    if templates.is_empty() {
+        templates = match lockfile {
+            Some(lockfile) => lockfile
+                .templates
+                .iter()
+                .map(|template| template.name.clone())
+                .collect(),
+            None => config.templates.clone().unwrap_or_default(),
+        };
+    }
+    if templates.is_empty() {
+        return Err(Error::Invalid(
+            "No templates given, and there are no default templates in the configuration file"
+                .to_string(),
+        ));
+    }
+    Ok(templates)
 }
-fn main_layer() -> impl View {
-    fn make_label(text: &str) -> impl View {
-        TextView::new(StyledString::styled(text, BaseColor::Yellow.dark())).h_align(HAlign::Center)
-    }
-    fn make_layout(label: &str, name: &str, on_submit: fn(&mut Cursive, &str)) -> impl View {
-        fn make_select_view(name: &str, on_submit: fn(&mut Cursive, &str)) -> impl View {
-            SelectView::<String>::new()
-                .on_submit(on_submit)
-                .with_name(name)
-                .scrollable()
-                .wrap_with(OnEventView::new)
-                .on_pre_event_inner(Event::CtrlChar('n'), |view, _event| {
-                    view.on_event(Event::Key(Key::Down));
-                    Some(EventResult::Consumed(None))
-                })
-                .on_pre_event_inner(Event::CtrlChar('p'), |view, _event| {
-                    view.on_event(Event::Key(Key::Up));
-                    Some(EventResult::Consumed(None))
-                })
+/// Fails if any of the templates isn't in the lockfile, or has changed since it was written.
+fn check_locked(client: &Client, lockfile: &Lockfile, template_names: &[String]) -> Result<()> {
+    // Compare with the templates as they are now, not with a cached copy
+    let templates = client.clone().require_current_templates(true).templates()?;
+    let mut changed = Vec::new();
+    for name in template_names {
+        let Some(template) = gig_gen::find_template(&templates, name) else {
+            return Err(Error::UnknownTemplate(name.clone()));
+        };
+        match lockfile.get(name) {
+            Some(locked) if locked.hash != lock::hash(&template.contents) => {
+                changed.push(name.as_str())
+            }
+            Some(_) => {}
+            None => {
+                return Err(Error::Locked(format!(
+                    r#"The "{}" template isn't in {}"#,
+                    name,
+                    lock::FILE_NAME
+                )))
+            }
         }
-        LinearLayout::vertical()
-            .child(make_label(label))
-            .child(make_select_view(name, on_submit))
-            .min_width(29)
-            .full_width()
-            .full_height()
     }
-
-    let lists_layout = LinearLayout::horizontal()
-        .child(make_layout(
-            " Available templates ",
-            AVAILABLE_VIEW_NAME,
-            select_item,
-        ))
-        .child(DummyView::new().fixed_width(4))
-        .child(make_layout(
-            " Selected templates ",
-            SELECTED_VIEW_NAME,
-            unselect_item,
+    if !changed.is_empty() {
+        return Err(Error::Locked(format!(
+            "These templates have changed since {} was written: {}",
+            lock::FILE_NAME,
+            changed.join(", ")
+        )));
+    }
+    Ok(())
+}
+/// Returns the path to write the output to, or `None` to write it to stdout.
+/// A `.gitignore` file is written to the configured output path if there isn't one in the arguments.
+fn output_path(args: &TemplateArgs) -> Option<PathBuf> {
+    match &args.output {
+        Some(path) if path.as_os_str() == "-" => None,
+        Some(path) => Some(path.clone()),
+        None if args.format == FileFormat::Gitignore
+            && args.output_format == OutputFormat::Plain =>
+        {
+            config::get().output.clone()
+        }
+        None => None,
+    }
+}
+/// Writes the rules each template adds to the generated file, leaving out the ones
+/// that repeat a rule from an earlier template.
+fn generate_template_rules(client: &Client, args: TemplateArgs) -> Result<()> {
+    #[derive(serde::Serialize)]
+    struct TemplateRules<'a> {
+        name: &'a str,
+        display_name: &'a str,
+        rules: Vec<String>,
+    }
+    #[derive(serde::Serialize)]
+    struct Output<'a> {
+        templates: Vec<TemplateRules<'a>>,
+    }
+    if args.format != FileFormat::Gitignore {
+        return Err(Error::Invalid(
+            "Only gitignore rules can be listed by template".to_string(),
         ));
-
-    let filter_layout = LinearLayout::horizontal()
-        .child(make_label("Filter:"))
-        .child(TextView::new(String::default()).with_name(FILTER_VIEW_NAME));
-
-    LinearLayout::vertical()
-        .child(lists_layout)
-        .child(DummyView::new())
-        .child(filter_layout)
+    }
+    let templates = client.templates()?;
+    let mut output = Output { templates: vec![] };
+    let mut previous_rules = String::default();
+    for name in &args.templates {
+        let Some(template) = gig_gen::find_template(&templates, name) else {
+            return Err(Error::UnknownTemplate(name.clone()));
+        };
+        let contents = gig_gen::combine(&previous_rules, &template.contents);
+        previous_rules += contents.as_str();
+        let rules = Gitignore::parse(&contents)
+            .lines()
+            .iter()
+            .filter(|line| line.rule().is_some())
+            .map(|line| line.text().to_string())
+            .collect();
+        output.templates.push(TemplateRules {
+            name: &template.key,
+            display_name: &template.name,
+            rules,
+        });
+    }
+    let text = if args.output_format == OutputFormat::Csv {
+        let rows: Vec<Vec<String>> = output
+            .templates
+            .iter()
+            .flat_map(|template| {
+                template
+                    .rules
+                    .iter()
+                    .map(|rule| vec![template.name.to_string(), rule.to_string()])
+            })
+            .collect();
+        output::csv(&["template", "rule"], &rows)
+    } else {
+        output::serialize(args.output_format, &output)
+    };
+    match args.output {
+        Some(path) => {
+            std::fs::write(&path, text).map_err(|error| Error::io(&path, error))?;
+        }
+        None => println!("{}", text),
+    }
+    Ok(())
+}
+fn write_output(output: &PathBuf, generated: &str, format: Format, mode: SaveMode) -> Result<()> {
+    let gitignore = gig_gen::saved_contents(output, generated, format, mode)?;
+    let dir = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    if format == Format::Gitignore {
+        let tracked_files = git::ignored_tracked_files(dir, &Gitignore::parse(&gitignore));
+        if !tracked_files.is_empty() {
+            print_message("Warning: these tracked files would be ignored:");
+            for path in tracked_files {
+                eprintln!("  {}", path);
+            }
+        }
+    }
+    std::fs::write(output, gitignore).map_err(|error| Error::io(output, error))
 }
-fn save(siv: &mut Cursive) {
-    siv.with_user_data(|user_data: &mut UserData| {
-        if user_data.templates.any_selected() {
-            let output_file = Path::new(OUTPUT_FILE_NAME);
-            if output_file.exists() {
-                user_data
-                    .cb_sink
-                    .send(Box::new(get_overwrite_choice))
-                    .expect("get overwrite choice failed");
+fn explain(args: PathArgs) -> Result<()> {
+    let Some(path) = relative_path(&args.path) else {
+        return Err(Error::Invalid(format!(
+            r#""{}" is not in the current directory"#,
+            args.path
+        )));
+    };
+    let gitignore = match std::fs::read_to_string(OUTPUT_FILE_NAME) {
+        Ok(gitignore) => Gitignore::parse(&gitignore),
+        Err(error) => return Err(Error::io(OUTPUT_FILE_NAME, error)),
+    };
+    let is_dir = args.path.ends_with('/') || Path::new(&args.path).is_dir();
+    match Matcher::new(&gitignore).explain(&path, is_dir) {
+        Some(matched) => {
+            let line = matched.line();
+            if matched.is_ignored() {
+                println!("{} is ignored", path);
             } else {
-                user_data
-                    .cb_sink
-                    .send(Box::new(create))
-                    .expect("create failed");
+                println!("{} is not ignored", path);
+            }
+            println!(
+                "  by {}:{}: {}",
+                OUTPUT_FILE_NAME,
+                line.number(),
+                line.text()
+            );
+            if let Some(section) = line.section() {
+                println!("  in the {} section", section);
+            }
+            if matched.path() != path {
+                println!(
+                    "  because its parent directory {} is ignored",
+                    matched.path()
+                );
             }
-        } else {
-            user_data
-                .cb_sink
-                .send(Box::new(nothing_to_save_warning))
-                .expect("save warning failed");
         }
-    });
+        None => println!("{} is not ignored by any rule", path),
+    }
+    Ok(())
 }
-fn get_overwrite_choice(siv: &mut Cursive) {
-    siv.add_layer(
-        Dialog::text("A .gitignore file already\rexists in the current directory.")
-            .h_align(HAlign::Center)
-            .button("Overwrite", |s| {
-                s.pop_layer();
-                s.user_data::<UserData>()
-                    .unwrap()
-                    .cb_sink
-                    .clone()
-                    .send(Box::new(overwrite))
-                    .expect("overwrite choice failed");
-            })
-            .button("Append", |s| {
-                s.pop_layer();
-                s.user_data::<UserData>()
-                    .unwrap()
-                    .cb_sink
-                    .clone()
-                    .send(Box::new(append))
-                    .expect("append choice failed");
-            })
-            .button("Cancel", |s| {
-                s.pop_layer();
-            }),
-    );
+fn audit(client: &Client) -> Result<()> {
+    const MAX_SUGGESTIONS: usize = 10;
+    const MAX_PATHS: usize = 5;
+    let dir = Path::new(".");
+    if !git::is_work_tree(dir) {
+        return Err(Error::Invalid(
+            "The current directory is not in a git repository".to_string(),
+        ));
+    }
+    let contents = std::fs::read_to_string(OUTPUT_FILE_NAME).unwrap_or_default();
+    let paths = git::untracked_paths(dir);
+    if paths.is_empty() {
+        println!("There are no untracked files that aren't ignored.");
+        return Ok(());
+    }
+    println!("Untracked files that aren't ignored:");
+    for path in &paths {
+        println!("  {}", path);
+    }
+    let templates = client.templates()?;
+    // Leave out the templates the .gitignore file was generated from
+    let used_templates = gig_gen::parse_template_names(&contents);
+    let templates: Vec<_> = templates
+        .into_iter()
+        .filter(|template| !used_templates.contains(&template.key))
+        .collect();
+    let suggestions = audit::suggest_templates(&paths, &templates);
+    if suggestions.is_empty() {
+        println!("No templates would ignore them.");
+        return Ok(());
+    }
+    println!();
+    println!("Templates that would ignore them:");
+    for suggestion in suggestions.iter().take(MAX_SUGGESTIONS) {
+        let mut paths = suggestion.paths[..suggestion.paths.len().min(MAX_PATHS)].join(", ");
+        if suggestion.paths.len() > MAX_PATHS {
+            paths += format!(" and {} more", suggestion.paths.len() - MAX_PATHS).as_str();
+        }
+        println!(
+            "  {} ({}): {}",
+            suggestion.template,
+            suggestion.paths.len(),
+            paths
+        );
+    }
+    Ok(())
 }
-fn save_gitignore(siv: &mut Cursive, save_option: SaveOption) -> bool {
-    fn get_gitignore(siv: &mut Cursive) -> Option<Result<String, minreq::Error>> {
-        siv.with_user_data(|user_data: &mut UserData| {
-            let selected_templates = user_data.templates.selected_template_names();
-            gitignore_api::get_template(&selected_templates)
-        })
+/// Checks the file. Exits with status 1 if any problems are left in it.
+fn lint(args: LintArgs) -> Result<ExitCode> {
+    let mut gitignore = match std::fs::read_to_string(&args.file) {
+        Ok(gitignore) => Gitignore::parse(&gitignore),
+        Err(error) => return Err(Error::io(&args.file, error)),
+    };
+    let mut fixed = None;
+    if args.fix {
+        let (text, fixes) = lint::fix(&gitignore);
+        if !fixes.is_empty() {
+            let mut backup = args.file.clone().into_os_string();
+            backup.push(".bak");
+            std::fs::copy(&args.file, &backup)
+                .and_then(|_| std::fs::write(&args.file, &text))
+                .map_err(|error| Error::io(&args.file, error))?;
+            gitignore = Gitignore::parse(&text);
+        }
+        fixed = Some(fixes);
     }
-    if let Some(gitignore) = get_gitignore(siv) {
-        let mut open_options = OpenOptions::new();
-        open_options.write(true);
-        match gitignore {
-            Ok(gitignore) => {
-                match save_option {
-                    SaveOption::Create => {
-                        open_options.create_new(true);
-                    }
-                    SaveOption::Overwrite => {
-                        open_options.truncate(true);
-                    }
-                    SaveOption::Append => {
-                        open_options.append(true);
-                    }
-                }
-                match open_options.open(OUTPUT_FILE_NAME) {
-                    Ok(mut file) => {
-                        if let Err(error) = file.write(gitignore.as_bytes()) {
-                            let message = format!("Error writing .gitignore file. [{}]", error);
-                            siv.add_layer(Dialog::info(message).h_align(HAlign::Center));
-                            false
-                        } else {
-                            true
-                        }
-                    }
-                    Err(error) => {
-                        let message = format!("Error opening .gitignore file. [{}]", error);
-                        siv.add_layer(Dialog::info(message).h_align(HAlign::Center));
-                        false
-                    }
-                }
+    let findings = lint::lint(&gitignore);
+    match args.format {
+        OutputFormat::Plain => {
+            for finding in fixed.iter().flatten() {
+                println!(
+                    "{}:{}: fixed {}: {}",
+                    args.file.display(),
+                    finding.line,
+                    finding.kind,
+                    finding.message
+                );
             }
-            Err(error) => {
-                let message = format!("Error fetching .gitignore data. [{}]", error);
-                siv.add_layer(Dialog::info(message).h_align(HAlign::Center));
-                false
+            for finding in &findings {
+                println!(
+                    "{}:{}: {}: {}",
+                    args.file.display(),
+                    finding.line,
+                    finding.kind,
+                    finding.message
+                );
             }
         }
-    } else {
-        panic!("No user data?");
+        OutputFormat::Csv => {
+            let row = |finding: &lint::Finding, is_fixed: bool| {
+                vec![
+                    args.file.display().to_string(),
+                    finding.line.to_string(),
+                    finding.kind.to_string(),
+                    finding.message.clone(),
+                    is_fixed.to_string(),
+                ]
+            };
+            let rows: Vec<Vec<String>> = fixed
+                .iter()
+                .flatten()
+                .map(|finding| row(finding, true))
+                .chain(findings.iter().map(|finding| row(finding, false)))
+                .collect();
+            println!(
+                "{}",
+                output::csv(&["file", "line", "kind", "message", "fixed"], &rows)
+            );
+        }
+        OutputFormat::Json | OutputFormat::Yaml => {
+            #[derive(serde::Serialize)]
+            struct Output<'a> {
+                file: &'a Path,
+                #[serde(skip_serializing_if = "Option::is_none")]
+                fixed: Option<&'a [lint::Finding]>,
+                findings: &'a [lint::Finding],
+            }
+            let output = Output {
+                file: &args.file,
+                fixed: fixed.as_deref(),
+                findings: &findings,
+            };
+            println!("{}", output::serialize(args.format, &output));
+        }
     }
-}
-fn overwrite(siv: &mut Cursive) {
-    if save_gitignore(siv, SaveOption::Overwrite) {
-        siv.with_user_data(|user_data: &mut UserData| {
-            user_data.final_message =
-                Some("Replaced contents of existing .gitignore file.".to_string());
-        });
-        siv.quit();
+    if !findings.is_empty() {
+        // Let scripts tell that there were problems
+        return Ok(ExitCode::from(1));
     }
+    Ok(ExitCode::SUCCESS)
 }
-fn append(siv: &mut Cursive) {
-    if save_gitignore(siv, SaveOption::Append) {
-        siv.with_user_data(|user_data: &mut UserData| {
-            user_data.final_message =
-                Some("Appended templates to existing .gitignore file.".to_string());
+/// Lists the templates that have changed. Exits with status 1 if any have.
+fn outdated(client: &Client, args: OutdatedArgs) -> Result<ExitCode> {
+    // A cached copy could say the templates are up to date when they aren't
+    let client = client.clone().require_current_templates(true);
+    let file = args
+        .file
+        .unwrap_or_else(|| config::output_file(Format::Gitignore));
+    let GeneratedFile {
+        gitignore,
+        lockfile,
+        blocks,
+    } = read_generated_file(&file)?;
+    let templates = client.templates()?;
+    let regenerated: Vec<Gitignore> = regenerate_blocks(&client, &gitignore, &blocks)?
+        .iter()
+        .map(|text| Gitignore::parse(text))
+        .collect();
+    let file_name = file.display().to_string();
+    let mut statuses = outdated::check(
+        &gitignore,
+        &blocks,
+        &regenerated,
+        lockfile.as_ref(),
+        &templates,
+        &file_name,
+    );
+    if !args.templates.is_empty() {
+        statuses.retain(|status| {
+            args.templates
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&status.template))
         });
-        siv.quit();
     }
+    let mut any_outdated = false;
+    for status in &statuses {
+        match status.state {
+            outdated::State::UpToDate => println!("{}: up to date", status.template),
+            outdated::State::Changed => println!("{}: changed", status.template),
+            outdated::State::Removed => println!("{}: no longer available", status.template),
+        }
+        if status.state != outdated::State::UpToDate {
+            any_outdated = true;
+        }
+        if args.diff && status.state == outdated::State::Changed {
+            if status.diff.is_empty() {
+                println!("  (none of the changes are in {})", file_name);
+            } else {
+                print!("{}", status.diff);
+            }
+        }
+    }
+    if any_outdated {
+        // Let scripts tell that there were changes
+        return Ok(ExitCode::from(1));
+    }
+    Ok(ExitCode::SUCCESS)
 }
-fn create(siv: &mut Cursive) {
-    if save_gitignore(siv, SaveOption::Create) {
-        siv.with_user_data(|user_data: &mut UserData| {
-            user_data.final_message = Some("Created new .gitignore file.".to_string());
-        });
-        siv.quit();
+fn upgrade(client: &Client, args: UpgradeArgs) -> Result<()> {
+    // Upgrade to the templates as they are now, not to a cached copy
+    let client = client.clone().require_current_templates(true);
+    let file = args
+        .file
+        .unwrap_or_else(|| config::output_file(Format::Gitignore));
+    let GeneratedFile {
+        gitignore,
+        lockfile,
+        blocks,
+    } = read_generated_file(&file)?;
+    if blocks.is_empty() {
+        return Err(Error::Invalid(format!(
+            "{} doesn't have any rules generated from templates",
+            file.display()
+        )));
+    }
+    let regenerated = regenerate_blocks(&client, &gitignore, &blocks)?;
+    let text = gitignore.to_string();
+    let upgraded_text = block::replace(&gitignore, &blocks, &regenerated);
+
+    // Record the current templates in the lockfile
+    let templates = client.templates()?;
+    let lock_path = lock::path(&file);
+    let mut upgraded_lockfile = lockfile.clone().unwrap_or_default();
+    let locked_names = upgraded_lockfile
+        .templates
+        .iter()
+        .map(|template| template.name.clone())
+        .collect::<Vec<_>>();
+    let template_names = locked_names
+        .iter()
+        .chain(blocks.iter().flat_map(|block| block.templates()));
+    upgraded_lockfile
+        .update(template_names.filter_map(|name| gig_gen::find_template(&templates, name)));
+
+    let file_name = file.display().to_string();
+    let diff = diff::unified(
+        &text.lines().collect::<Vec<_>>(),
+        &upgraded_text.lines().collect::<Vec<_>>(),
+        &file_name,
+        format!("{} (upgraded)", file_name).as_str(),
+    );
+    let is_lockfile_changed = lockfile.as_ref() != Some(&upgraded_lockfile);
+    if diff.is_empty() && !is_lockfile_changed {
+        println!("{} is up to date.", file_name);
+        return Ok(());
+    }
+    if diff.is_empty() {
+        println!("Only {} needs to be updated.", lock_path.display());
+    } else {
+        print!("{}", diff);
+    }
+    if !args.yes && !confirm("Save the changes?") {
+        println!("Nothing was changed.");
+        return Ok(());
     }
+    if !diff.is_empty() {
+        std::fs::write(&file, &upgraded_text).map_err(|error| Error::io(&file, error))?;
+    }
+    upgraded_lockfile.write(&lock_path)?;
+    println!("Upgraded {}.", file_name);
+    Ok(())
 }
-fn nothing_to_save_warning(siv: &mut Cursive) {
-    siv.add_layer(
-        Dialog::info("Select one or more templates and try again.").h_align(HAlign::Center),
+fn serve(client: &Client, args: ServeArgs) -> Result<()> {
+    let mut templates = client.templates()?;
+    if let Some(dir) = &args.templates {
+        let private_templates = server::read_templates(dir)?;
+        templates
+            .retain(|template| gig_gen::find_template(&private_templates, &template.key).is_none());
+        templates.extend(private_templates);
+        templates.sort_by(|a, b| a.key.cmp(&b.key));
+    }
+    let listener = TcpListener::bind(&args.bind)
+        .map_err(|error| Error::Invalid(format!("Can't listen on {}: {}", args.bind, error)))?;
+    let address = listener
+        .local_addr()
+        .map_err(|error| Error::Invalid(error.to_string()))?;
+    print_message(
+        format!(
+            "Serving {} templates at http://{}/api",
+            templates.len(),
+            address
+        )
+        .as_str(),
     );
+    server::serve(&listener, templates);
+    Ok(())
 }
-fn select_item(siv: &mut Cursive, selection: &str) {
-    siv.with_user_data(|user_data: &mut UserData| {
-        user_data.templates.select_template(selection);
-    });
-    refresh(siv);
+/// A `.gitignore` file with its lockfile and the blocks in it that were generated from templates.
+struct GeneratedFile {
+    gitignore: Gitignore,
+    lockfile: Option<Lockfile>,
+    blocks: Vec<GeneratedBlock>,
 }
-fn unselect_item(siv: &mut Cursive, selection: &str) {
-    siv.with_user_data(|user_data: &mut UserData| {
-        user_data.templates.unselect_template(selection);
-    });
-    refresh(siv);
+/// Reads a `.gitignore` file and its lockfile, and finds the file's generated blocks.
+/// Fails if the file wasn't generated from templates and doesn't have a lockfile.
+fn read_generated_file(file: &Path) -> Result<GeneratedFile> {
+    let gitignore = match std::fs::read_to_string(file) {
+        Ok(gitignore) => Gitignore::parse(&gitignore),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Gitignore::default(),
+        Err(error) => return Err(Error::io(file, error)),
+    };
+    let lock_path = lock::path(file);
+    let lockfile = Lockfile::read(&lock_path)?;
+    let blocks = block::find_blocks(&gitignore);
+    if blocks.is_empty() && lockfile.is_none() {
+        return Err(Error::Invalid(format!(
+            "{} wasn't generated from templates, and there is no {} file",
+            file.display(),
+            lock_path.display()
+        )));
+    }
+    Ok(GeneratedFile {
+        gitignore,
+        lockfile,
+        blocks,
+    })
 }
-fn refresh(siv: &mut Cursive) {
-    let mut available_view = siv
-        .find_name::<SelectView<String>>(AVAILABLE_VIEW_NAME)
-        .unwrap();
-    let available_index = available_view.selected_id().unwrap_or_default();
-
-    let mut selected_view = siv.find_name::<SelectView>(SELECTED_VIEW_NAME).unwrap();
-
-    let mut filter_view = siv.find_name::<TextView>(FILTER_VIEW_NAME).unwrap();
-
-    siv.with_user_data(|user_data: &mut UserData| {
-        // Display the filter
-        filter_view.set_content(format!(" {}", user_data.filter));
-
-        // Display the possibly filtered list of available templates
-        available_view.clear();
-        user_data
-            .templates
-            .unselected_templates()
-            .iter()
-            .filter_map(|template| {
-                if !user_data.filter.is_empty()
-                    && !template.name().starts_with(user_data.filter.as_str())
-                {
-                    None
-                } else {
-                    Some(template.name())
-                }
-            })
-            .for_each(|template_name| available_view.add_item_str(template_name));
-
-        // Set the selected item unless the list has just been filtered
-        if !user_data.new_filter {
-            available_view.set_selection(available_index);
-            user_data.new_filter = false;
+/// Returns what each of the generated blocks would contain if it were generated now.
+fn regenerate_blocks(
+    client: &Client,
+    gitignore: &Gitignore,
+    blocks: &[GeneratedBlock],
+) -> Result<Vec<String>> {
+    let mut regenerated = Vec::new();
+    for block in blocks {
+        let template_text = client.fetch(block.templates())?;
+        regenerated.push(block::regenerate(gitignore, block, &template_text));
+    }
+    Ok(regenerated)
+}
+/// Asks a yes or no question on the terminal. Returns true if the answer is yes.
+fn confirm(question: &str) -> bool {
+    eprint!("{} [y/N] ", question);
+    let _ = std::io::stderr().flush();
+    let mut answer = String::default();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+/// Returns `path` relative to the current directory, with `/` separators,
+/// or `None` if it is outside the current directory.
+fn relative_path(path: &str) -> Option<String> {
+    let mut path = Path::new(path);
+    let current_dir = std::env::current_dir().ok()?;
+    if path.is_absolute() {
+        path = path.strip_prefix(&current_dir).ok()?;
+    }
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_str()?),
+            Component::CurDir => {}
+            _ => return None,
         }
-
-        // Display the list of selected templates
-        selected_view.clear();
-        user_data
-            .templates
-            .selected_templates()
-            .iter()
-            .for_each(|option| selected_view.add_item_str(option.name()));
-    });
+    }
+    Some(components.join("/"))
 }
-fn help(siv: &mut Cursive) {
-    let message = "Use this app to create a .gitignore file for one or more operating systems, programming languages or IDEs, using templates from https://www.toptal.com/developers/gitignore/
-
-Select the templates to include in the file.
-- Use the up and down arrows to highlight a template.
-- Press Enter to select the highlighted template.
-- Type the start of the template's name to filter the list.
-
-Press Ctrl+S to write the .gitignore file to disk.
-- The .gitignore file will be written to the current directory.
-- If the .gitignore file already exists, you will be given the option of replacing it or appending to it.
-
-Press Ctrl+Q to close the app without writing the .gitignore file.";
-    siv.add_layer(Dialog::info(message).h_align(HAlign::Center));
+/// Runs the interactive app in the terminal.
+fn interactive(client: Client) -> Result<()> {
+    let backend = cursive::backends::try_default()
+        .map_err(|error| Error::Invalid(format!("Can't start the interactive app: {}", error)))?;
+    if let Some(final_message) = interactive::run(backend, Box::new(client), Path::new("")) {
+        print_message(&final_message);
+    }
+    Ok(())
 }
-fn about(siv: &mut Cursive) {
-    let mut styled = StyledString::styled("+---------------+\n", BaseColor::Yellow.dark());
-    styled.append(StyledString::styled("|", BaseColor::Yellow.dark()));
-    styled.append(StyledString::plain(" g i g - g e n "));
-    styled.append(StyledString::styled("|\n", BaseColor::Yellow.dark()));
-    styled.append(StyledString::styled(
-        "+---------------+\n",
-        BaseColor::Yellow.dark(),
-    ));
-    styled.append(StyledString::plain(format!(
-        "v {}\n",
-        env!("CARGO_PKG_VERSION")
-    )));
-    styled.append(StyledString::plain("Copyright © 2024 Paul Sobolik\n\n"));
-    styled.append(StyledString::plain("API and templates provided by\n"));
-    styled.append(StyledString::plain(
-        "https://www.toptal.com/developers/gitignore/",
-    ));
-
-    siv.add_layer(
-        Dialog::around(TextView::new(styled).h_align(HAlign::Center))
-            .h_align(HAlign::Center)
-            .button("Ok", |s| {
-                s.pop_layer();
-            }),
-    );
+fn print_message(message: &str) {
+    eprintln!("[{}] \x1b[93m{}\x1b[0m", env!["CARGO_PKG_NAME"], message);
 }
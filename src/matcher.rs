@@ -0,0 +1,201 @@
+use crate::gitignore::{Gitignore, Line, Rule};
+use regex::Regex;
+
+/// Matches paths against the rules of a `.gitignore` file.
+///
+/// Paths are `/`-separated and relative to the `.gitignore` file's directory.
+#[derive(Clone)]
+pub struct Matcher<'a> {
+    rules: Vec<(&'a Line, &'a Rule, Regex)>,
+}
+
+/// The rule that decides whether a path is ignored.
+pub struct Match<'a> {
+    line: &'a Line,
+    path: String,
+    is_ignored: bool,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(gitignore: &'a Gitignore) -> Self {
+        let rules = gitignore
+            .lines()
+            .iter()
+            .filter_map(|line| {
+                let rule = line.rule()?;
+                let regex = Regex::new(&rule.to_regex()).ok()?;
+                Some((line, rule, regex))
+            })
+            .collect();
+        Self { rules }
+    }
+    /// Returns a matcher for the rules that come before `line`.
+    pub fn before(&self, line: &Line) -> Self {
+        let rules = self
+            .rules
+            .iter()
+            .take_while(|(l, _, _)| l.number() < line.number())
+            .cloned()
+            .collect();
+        Self { rules }
+    }
+    /// Returns the rules that come after `line`.
+    pub fn after<'b>(&'b self, line: &'b Line) -> impl Iterator<Item = (&'a Line, &'a Rule)> + 'b {
+        self.rules
+            .iter()
+            .skip_while(move |(l, _, _)| l.number() <= line.number())
+            .map(|(line, rule, _)| (*line, *rule))
+    }
+    /// Returns the rule that decides whether `path` is ignored, or `None` if no rule matches it.
+    ///
+    /// As in git, the last matching rule wins, and a path inside an ignored
+    /// directory is ignored even if a later rule would re-include it.
+    pub fn explain(&self, path: &str, is_dir: bool) -> Option<Match<'a>> {
+        let path = path.trim_start_matches("./").trim_end_matches('/');
+        for (index, _) in path.match_indices('/') {
+            let parent = &path[..index];
+            if let Some((line, rule)) = self.last_match(parent, true) {
+                if !rule.is_negated() {
+                    return Some(Match {
+                        line,
+                        path: parent.to_string(),
+                        is_ignored: true,
+                    });
+                }
+            }
+        }
+        self.last_match(path, is_dir).map(|(line, rule)| Match {
+            line,
+            path: path.to_string(),
+            is_ignored: !rule.is_negated(),
+        })
+    }
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        self.explain(path, is_dir)
+            .is_some_and(|matched| matched.is_ignored())
+    }
+    fn last_match(&self, path: &str, is_dir: bool) -> Option<(&'a Line, &'a Rule)> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(_, rule, regex)| (is_dir || !rule.is_directory_only()) && regex.is_match(path))
+            .map(|(line, rule, _)| (*line, *rule))
+    }
+}
+
+impl<'a> Match<'a> {
+    pub fn line(&self) -> &'a Line {
+        self.line
+    }
+    /// The path the rule matched, which is a parent directory of the path being
+    /// matched if that directory is ignored.
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+    pub fn is_ignored(&self) -> bool {
+        self.is_ignored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the number of the line that decides whether `path` is ignored, and whether it is.
+    fn explain(gitignore: &str, path: &str, is_dir: bool) -> Option<(usize, bool)> {
+        let gitignore = Gitignore::parse(gitignore);
+        let matcher = Matcher::new(&gitignore);
+        matcher
+            .explain(path, is_dir)
+            .map(|matched| (matched.line().number(), matched.is_ignored()))
+    }
+
+    #[test]
+    fn no_rule_matches() {
+        assert_eq!(explain("*.log\n", "main.rs", false), None);
+        assert_eq!(explain("", "main.rs", false), None);
+    }
+
+    #[test]
+    fn last_match_wins() {
+        let gitignore = "*.log\n!important.log\n";
+        assert_eq!(explain(gitignore, "debug.log", false), Some((1, true)));
+        assert_eq!(explain(gitignore, "important.log", false), Some((2, false)));
+        let gitignore = "*.log\n!important.log\n*.log\n";
+        assert_eq!(explain(gitignore, "important.log", false), Some((3, true)));
+    }
+
+    #[test]
+    fn negation() {
+        let gitignore = Gitignore::parse("*.env\n!.env.example\n");
+        let matcher = Matcher::new(&gitignore);
+        assert!(matcher.is_ignored("prod.env", false));
+        assert!(!matcher.is_ignored(".env.example", false));
+        assert!(!matcher.is_ignored("readme.md", false));
+    }
+
+    #[test]
+    fn directory_only() {
+        let gitignore = "build/\n";
+        assert_eq!(explain(gitignore, "build", true), Some((1, true)));
+        assert_eq!(explain(gitignore, "build", false), None);
+        assert_eq!(explain(gitignore, "src/build", true), Some((1, true)));
+        // A file in the directory is ignored because the directory is
+        assert_eq!(explain(gitignore, "build/out.o", false), Some((1, true)));
+    }
+
+    #[test]
+    fn anchoring() {
+        let gitignore = "/dist\ndocs/api\nnode_modules\n";
+        assert_eq!(explain(gitignore, "dist", true), Some((1, true)));
+        assert_eq!(explain(gitignore, "web/dist", true), None);
+        assert_eq!(explain(gitignore, "docs/api", true), Some((2, true)));
+        assert_eq!(explain(gitignore, "web/docs/api", true), None);
+        assert_eq!(
+            explain(gitignore, "web/node_modules", true),
+            Some((3, true))
+        );
+    }
+
+    #[test]
+    fn parent_directory_excluded() {
+        // A file can't be re-included if its directory is ignored
+        let gitignore = "logs/\n!logs/keep.log\n";
+        let gitignore = Gitignore::parse(gitignore);
+        let matcher = Matcher::new(&gitignore);
+        let matched = matcher.explain("logs/keep.log", false).unwrap();
+        assert!(matched.is_ignored());
+        assert_eq!(matched.line().number(), 1);
+        assert_eq!(matched.path(), "logs");
+        // It can be if only the directory's contents are ignored
+        let gitignore = Gitignore::parse("logs/*\n!logs/keep.log\n");
+        let matcher = Matcher::new(&gitignore);
+        assert!(!matcher.is_ignored("logs/keep.log", false));
+        assert!(matcher.is_ignored("logs/other.log", false));
+        // A re-included directory isn't excluded
+        let gitignore = Gitignore::parse("*\n!src/\n");
+        let matcher = Matcher::new(&gitignore);
+        assert!(matcher.is_ignored("src/main.rs", false));
+        assert!(!matcher.is_ignored("src", true));
+    }
+
+    #[test]
+    fn paths_are_normalized() {
+        let gitignore = "/target/\n";
+        assert_eq!(explain(gitignore, "./target/", true), Some((1, true)));
+        assert_eq!(explain(gitignore, "target/debug", true), Some((1, true)));
+    }
+
+    #[test]
+    fn before_and_after() {
+        let gitignore = Gitignore::parse("*.log\n# comment\n!a.log\nb.log\n");
+        let matcher = Matcher::new(&gitignore);
+        let line = &gitignore.lines()[2];
+        assert!(matcher.before(line).is_ignored("a.log", false));
+        let after: Vec<String> = matcher
+            .after(line)
+            .map(|(_, rule)| rule.to_string())
+            .collect();
+        assert_eq!(after, ["b.log"]);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::gitignore::{Gitignore, Rule};
+
+/// Removes the rules in `gitignore` that repeat a rule already in effect, either earlier
+/// in `gitignore` itself or in the `existing` contents it is going to be appended to.
+///
+/// A repeated rule is only removed if no rule of the opposite kind (a negation for a
+/// pattern, or a pattern for a negation) comes between it and its previous occurrence,
+/// because in that case the repetition changes which paths are ignored.
+/// Comments and blank lines are left alone.
+pub fn dedupe(existing: &str, gitignore: &str) -> String {
+    let mut rules: Vec<Rule> = Gitignore::parse(existing).rules().cloned().collect();
+    let mut gitignore = Gitignore::parse(gitignore);
+    gitignore.retain(|line| match line.rule() {
+        Some(rule) if is_redundant(&rules, rule) => false,
+        Some(rule) => {
+            rules.push(rule.clone());
+            true
+        }
+        None => true,
+    });
+    gitignore.to_string()
+}
+
+/// Returns true if `rule` repeats one of `rules` without a rule of the opposite kind in between.
+pub fn is_redundant(rules: &[Rule], rule: &Rule) -> bool {
+    match rules.iter().rposition(|r| r == rule) {
+        Some(index) => rules[index + 1..]
+            .iter()
+            .all(|r| r.is_negated() == rule.is_negated()),
+        None => false,
+    }
+}
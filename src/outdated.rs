@@ -0,0 +1,119 @@
+use crate::block::GeneratedBlock;
+use crate::diff;
+use crate::gitignore::{Gitignore, Line};
+use crate::gitignore_api::{self, TemplateInfo};
+use crate::lock::{self, Lockfile};
+use std::collections::HashSet;
+
+/// Whether a template a `.gitignore` file was generated from has changed upstream.
+pub struct Status {
+    pub template: String,
+    pub state: State,
+    /// A diff of the template's rules in the file and its current rules, if it's in the file
+    pub diff: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    UpToDate,
+    Changed,
+    /// The template isn't available anymore
+    Removed,
+}
+
+/// Compares the templates recorded in `lockfile` and in the generated `blocks` of
+/// `gitignore` with the current `templates`.
+///
+/// `regenerated` has what each block would contain if it were generated now. A template
+/// in the lockfile is compared by its hash, and any other template by its rules in the file.
+pub fn check(
+    gitignore: &Gitignore,
+    blocks: &[GeneratedBlock],
+    regenerated: &[Gitignore],
+    lockfile: Option<&Lockfile>,
+    templates: &[TemplateInfo],
+    file_name: &str,
+) -> Vec<Status> {
+    let mut names: Vec<String> = Vec::new();
+    let locked_names = lockfile
+        .iter()
+        .flat_map(|lockfile| lockfile.templates.iter().map(|template| &template.name));
+    let block_names = blocks.iter().flat_map(|block| block.templates());
+    for name in locked_names.chain(block_names) {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            names.push(name.clone());
+        }
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            let Some(template) = gitignore_api::find_template(templates, &name) else {
+                return Status {
+                    template: name,
+                    state: State::Removed,
+                    diff: String::default(),
+                };
+            };
+            // The lines of the template's sections, in the file and as they would be now
+            let sections = section_names(&template.contents);
+            let mut old = Vec::new();
+            let mut new = Vec::new();
+            for (block, regenerated) in blocks.iter().zip(regenerated) {
+                if block.contains(&name) {
+                    old.extend(section_lines(block.lines(gitignore), &sections));
+                    new.extend(section_lines(regenerated.lines(), &sections));
+                }
+            }
+            let diff = diff::unified(
+                &old,
+                &new,
+                format!("{} ({})", file_name, template.key).as_str(),
+                format!("upstream ({})", template.key).as_str(),
+            );
+            let is_changed = match lockfile.and_then(|lockfile| lockfile.get(&name)) {
+                Some(locked) => locked.hash != lock::hash(&template.contents),
+                None => !diff.is_empty(),
+            };
+            Status {
+                template: template.key.clone(),
+                state: if is_changed {
+                    State::Changed
+                } else {
+                    State::UpToDate
+                },
+                diff,
+            }
+        })
+        .collect()
+}
+/// Returns the names of the `### Name ###` sections in a template.
+fn section_names(contents: &str) -> HashSet<String> {
+    Gitignore::parse(contents)
+        .lines()
+        .iter()
+        .filter_map(|line| line.section())
+        .map(|section| section.to_string())
+        .collect()
+}
+/// Returns the text of the lines in `sections`, without the blank lines at the end of each section.
+fn section_lines<'a>(lines: &'a [Line], sections: &HashSet<String>) -> Vec<&'a str> {
+    let mut texts: Vec<&str> = Vec::new();
+    let mut previous_section = None;
+    for line in lines {
+        let section = line.section().filter(|section| sections.contains(*section));
+        if section != previous_section {
+            trim_blank_lines(&mut texts);
+            previous_section = section;
+        }
+        if section.is_some() {
+            texts.push(line.text());
+        }
+    }
+    trim_blank_lines(&mut texts);
+    texts
+}
+fn trim_blank_lines(texts: &mut Vec<&str>) {
+    while texts.last().is_some_and(|text| text.trim().is_empty()) {
+        texts.pop();
+    }
+}
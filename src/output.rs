@@ -0,0 +1,34 @@
+use crate::args::OutputFormat;
+
+/// Formats `value` as JSON or YAML.
+pub(crate) fn serialize<T: serde::Serialize>(format: OutputFormat, value: &T) -> String {
+    // The values written are plain structs, which can always be serialized
+    match format {
+        OutputFormat::Yaml => serde_yaml::to_string(value)
+            .expect("Can't serialize the output")
+            .trim_end()
+            .to_string(),
+        _ => serde_json::to_string_pretty(value).expect("Can't serialize the output"),
+    }
+}
+/// Formats a header and rows as CSV, quoting the fields that need it.
+pub(crate) fn csv(header: &[&str], rows: &[Vec<String>]) -> String {
+    fn field(field: &str) -> String {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!(r#""{}""#, field.replace('"', r#""""#))
+        } else {
+            field.to_string()
+        }
+    }
+    let mut csv = header.join(",");
+    for row in rows {
+        csv.push('\n');
+        csv += row
+            .iter()
+            .map(|value| field(value))
+            .collect::<Vec<_>>()
+            .join(",")
+            .as_str();
+    }
+    csv
+}
@@ -0,0 +1,150 @@
+use crate::block;
+use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::gitignore::Gitignore;
+use crate::normalize;
+use std::io::Write;
+use std::path::Path;
+
+/// How [`save`] writes generated rules to a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaveMode {
+    /// Create a new file. Fails if the file already exists.
+    Create,
+    /// Replace the file's contents.
+    Overwrite,
+    /// Add the rules to the end of the file, leaving out the ones that are already in it,
+    /// except in an `.hgignore` file.
+    Append,
+    /// Replace the parts of the file that were generated from templates, and keep the rest.
+    /// Works like `Append` if no part of the file was generated from templates.
+    Update,
+}
+
+/// Returns what the file at `path` will contain after the `generated` rules, written in
+/// `format`, are saved to it.
+pub fn saved_contents(
+    path: &Path,
+    generated: &str,
+    format: Format,
+    mode: SaveMode,
+) -> Result<String> {
+    if matches!(mode, SaveMode::Create | SaveMode::Overwrite) {
+        return Ok(generated.to_string());
+    }
+    let existing = match std::fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::default(),
+        Err(error) => return Err(Error::io(path, error)),
+    };
+    let gitignore = Gitignore::parse(&existing);
+    let blocks = block::find_blocks(&gitignore);
+    match blocks.first() {
+        Some(first) if mode == SaveMode::Update => {
+            // The new rules replace the first generated block, and the others are removed
+            let mut contents = vec![String::default(); blocks.len()];
+            contents[0] = if can_dedupe(format) {
+                block::regenerate(&gitignore, first, generated)
+            } else {
+                generated.trim_end_matches('\n').to_string()
+            };
+            Ok(block::replace(&gitignore, &blocks, &contents))
+        }
+        _ => {
+            let mut text = existing.clone();
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            if can_dedupe(format) {
+                Ok(text + normalize::dedupe(&existing, generated).as_str())
+            } else {
+                Ok(text + generated)
+            }
+        }
+    }
+}
+/// Saves the `generated` rules, written in `format`, to the file at `path`.
+pub fn save(path: &Path, generated: &str, format: Format, mode: SaveMode) -> Result<()> {
+    let contents = saved_contents(path, generated, format, mode)?;
+    let result = match mode {
+        SaveMode::Create => std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(contents.as_bytes())),
+        _ => std::fs::write(path, contents),
+    };
+    result.map_err(|error| Error::io(path, error))
+}
+/// Returns true if the rules already in a file in `format` can be left out of the rules
+/// saved to it. Mercurial's `syntax:` lines change how the lines after them are read, so
+/// the same line in two places in an `.hgignore` file isn't always the same rule.
+fn can_dedupe(format: Format) -> bool {
+    format != Format::Hgignore
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saves `generated` to a file that contains `existing`, and returns the file's contents.
+    fn saved(
+        name: &str,
+        existing: &str,
+        generated: &str,
+        format: Format,
+        mode: SaveMode,
+    ) -> String {
+        let path =
+            std::env::temp_dir().join(format!("gig-gen-save-{}-{}", std::process::id(), name));
+        std::fs::write(&path, existing).unwrap();
+        let contents = saved_contents(&path, generated, format, mode);
+        std::fs::remove_file(&path).unwrap();
+        contents.unwrap()
+    }
+
+    #[test]
+    fn append_leaves_out_rules_already_in_the_file() {
+        let contents = saved(
+            "append",
+            "*.log\n",
+            "*.log\n*.swp\n",
+            Format::Gitignore,
+            SaveMode::Append,
+        );
+        assert_eq!(contents, "*.log\n*.swp\n");
+    }
+
+    #[test]
+    fn append_to_hgignore_keeps_syntax_lines() {
+        let existing = "syntax: glob\n*.log\nsyntax: regexp\n^target(?:/|$)\n";
+        let contents = saved(
+            "append-hg",
+            existing,
+            "syntax: glob\n*.swp\n",
+            Format::Hgignore,
+            SaveMode::Append,
+        );
+        assert_eq!(
+            contents,
+            "syntax: glob\n*.log\nsyntax: regexp\n^target(?:/|$)\nsyntax: glob\n*.swp\n"
+        );
+    }
+
+    #[test]
+    fn update_hgignore_keeps_syntax_lines() {
+        let existing = "syntax: glob\n*.swp\n# Created by https://example.com/api/rust\nsyntax: glob\n*.rs.bk\n# End of https://example.com/api/rust\n";
+        let generated = "# Created by https://example.com/api/rust\nsyntax: glob\n*.rs.bk\n*.pdb\n# End of https://example.com/api/rust\n";
+        let contents = saved(
+            "update-hg",
+            existing,
+            generated,
+            Format::Hgignore,
+            SaveMode::Update,
+        );
+        assert_eq!(
+            contents,
+            "syntax: glob\n*.swp\n# Created by https://example.com/api/rust\nsyntax: glob\n*.rs.bk\n*.pdb\n# End of https://example.com/api/rust\n"
+        );
+    }
+}
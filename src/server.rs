@@ -0,0 +1,433 @@
+use crate::error::{Error, Result};
+use crate::gitignore_api::{self, TemplateInfo};
+use crate::lock;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// How long to wait for a client to send its request, or to take the response.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// The longest request line or header line that's read, in bytes.
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+/// The most headers a request can have.
+const MAX_HEADERS: usize = 100;
+/// How much of a rejected request is read before the connection is closed, in bytes.
+const MAX_DISCARDED_LENGTH: u64 = 64 * 1024;
+/// The most connections that are handled at the same time. Others wait to be accepted.
+const MAX_CONNECTIONS: usize = 64;
+
+/// A response to a request.
+struct Response {
+    status: u16,
+    reason: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Response {
+            status: 200,
+            reason: "OK",
+            content_type,
+            body,
+        }
+    }
+    fn error(status: u16, reason: &'static str, body: String) -> Self {
+        Response {
+            status,
+            reason,
+            content_type: "text/plain; charset=utf-8",
+            body,
+        }
+    }
+}
+
+/// Serves `templates` on `listener` with the same routes and responses as the gitignore.io API:
+/// `/api/list`, `/api/list?format=json`, `/api/list?format=lines` and `/api/<names>`.
+/// Each connection is handled on its own thread, up to 64 at a time. Doesn't return.
+pub fn serve(listener: &TcpListener, templates: Vec<TemplateInfo>) {
+    let templates = Arc::new(templates);
+    let connections = Arc::new((Mutex::new(0), Condvar::new()));
+    loop {
+        // Wait for a connection to close before accepting another one
+        let (count, available) = &*connections;
+        drop(
+            available
+                .wait_while(count.lock().unwrap(), |count| *count >= MAX_CONNECTIONS)
+                .unwrap(),
+        );
+        let Ok((stream, _)) = listener.accept() else {
+            continue;
+        };
+        *count.lock().unwrap() += 1;
+        let templates = Arc::clone(&templates);
+        let connections = Arc::clone(&connections);
+        std::thread::spawn(move || {
+            // The client went away, or sent something that isn't HTTP
+            let _ = handle(stream, &templates);
+            let (count, available) = &*connections;
+            *count.lock().unwrap() -= 1;
+            available.notify_one();
+        });
+    }
+}
+/// Reads the `<Name>.gitignore` files in `dir` as templates, ordered by name. A template's key
+/// is its file name in lowercase, and its contents start with a `### Name ###` heading.
+pub fn read_templates(dir: &Path) -> Result<Vec<TemplateInfo>> {
+    let mut templates = BTreeMap::new();
+    let entries = std::fs::read_dir(dir).map_err(|error| Error::io(dir, error))?;
+    for entry in entries {
+        let path = entry.map_err(|error| Error::io(dir, error))?.path();
+        if path.extension() != Some(OsStr::new("gitignore")) {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let text = std::fs::read_to_string(&path).map_err(|error| Error::io(&path, error))?;
+        let key = name.to_lowercase();
+        templates.insert(
+            key.clone(),
+            TemplateInfo {
+                key,
+                name: name.to_string(),
+                contents: format!("\n### {} ###\n{}\n", name, text.trim_end()),
+                source: dir.display().to_string(),
+                cached: false,
+            },
+        );
+    }
+    Ok(templates.into_values().collect())
+}
+
+fn handle(stream: TcpStream, templates: &[TemplateInfo]) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::default();
+    if !read_line(&mut reader, &mut request_line)? {
+        return reject(&stream, reader, 414, "URI Too Long");
+    }
+    let mut host = None;
+    let mut if_none_match = None;
+    let mut header_count = 0;
+    loop {
+        let mut line = String::default();
+        if !read_line(&mut reader, &mut line)? || header_count == MAX_HEADERS {
+            return reject(&stream, reader, 431, "Request Header Fields Too Large");
+        }
+        if line.trim_end().is_empty() {
+            break;
+        }
+        header_count += 1;
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim().to_string();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "host" => host = Some(value),
+                "if-none-match" => if_none_match = Some(value),
+                _ => {}
+            }
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let response = match (method, host) {
+        // The host is written into the generated file, so it has to be a host name or address
+        (_, Some(host)) if !is_valid_host(&host) => {
+            Response::error(400, "Bad Request", "Invalid Host header".to_string())
+        }
+        ("GET" | "HEAD", host) => {
+            let host = match host {
+                Some(host) => host,
+                None => stream.local_addr()?.to_string(),
+            };
+            route(templates, &host, target)
+        }
+        _ => Response::error(405, "Method Not Allowed", String::default()),
+    };
+    write_response(
+        &stream,
+        &response,
+        method == "HEAD",
+        if_none_match.as_deref(),
+    )
+}
+/// Reads a line of the request into `line`, up to [`MAX_LINE_LENGTH`] bytes. Returns false if
+/// the line is longer than that. The end of the request counts as an empty line.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> std::io::Result<bool> {
+    let length = reader.take(MAX_LINE_LENGTH).read_line(line)?;
+    Ok(length < MAX_LINE_LENGTH as usize || line.ends_with('\n'))
+}
+/// Answers a request that's too large to read with `status`. The rest of the request is read
+/// and thrown away, up to [`MAX_DISCARDED_LENGTH`] bytes, so that closing the connection
+/// doesn't reset it before the client reads the response.
+fn reject(
+    stream: &TcpStream,
+    reader: impl Read,
+    status: u16,
+    reason: &'static str,
+) -> std::io::Result<()> {
+    write_response(
+        stream,
+        &Response::error(status, reason, String::default()),
+        false,
+        None,
+    )?;
+    stream.shutdown(Shutdown::Write)?;
+    std::io::copy(&mut reader.take(MAX_DISCARDED_LENGTH), &mut std::io::sink())?;
+    Ok(())
+}
+/// Returns true if `host` is a host name or IP address, with or without a port.
+fn is_valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 255
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+}
+/// Returns the response to a request for `target` from a client that reached the server at `host`.
+fn route(templates: &[TemplateInfo], host: &str, target: &str) -> Response {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let Some(names) = path.strip_prefix("/api/") else {
+        return Response::error(404, "Not Found", String::default());
+    };
+    let names = percent_decode(names);
+    if names == "list" {
+        let format = query
+            .split('&')
+            .find_map(|parameter| parameter.strip_prefix("format="))
+            .unwrap_or_default();
+        return list(templates, format);
+    }
+    let names: Vec<String> = names
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .collect();
+    let url = format!("http://{}/api/{}", host, names.join(","));
+    match gitignore_api::generate(&url, templates, &names) {
+        Ok(text) => Response::ok("text/plain; charset=utf-8", text),
+        // The same error as the API's
+        Err(Error::UnknownTemplate(name)) => Response::error(
+            404,
+            "Not Found",
+            format!(
+                "\n#!! ERROR: {} is undefined. Use list command to see defined gitignore types !!#\n",
+                name
+            ),
+        ),
+        Err(error) => Response::error(500, "Internal Server Error", error.to_string()),
+    }
+}
+/// Returns the list of templates in `format`: `json`, `lines`, or names separated by commas.
+fn list(templates: &[TemplateInfo], format: &str) -> Response {
+    #[derive(serde::Serialize)]
+    struct Entry<'a> {
+        key: &'a str,
+        name: &'a str,
+        #[serde(rename = "fileName")]
+        file_name: String,
+        contents: &'a str,
+    }
+    let keys = templates.iter().map(|template| template.key.as_str());
+    match format {
+        "json" => {
+            let entries: BTreeMap<&str, Entry> = templates
+                .iter()
+                .map(|template| {
+                    let entry = Entry {
+                        key: &template.key,
+                        name: &template.name,
+                        file_name: format!("{}.gitignore", template.name),
+                        contents: &template.contents,
+                    };
+                    (template.key.as_str(), entry)
+                })
+                .collect();
+            let json = serde_json::to_string(&entries).expect("Can't serialize the templates");
+            Response::ok("application/json; charset=utf-8", json)
+        }
+        "lines" => Response::ok(
+            "text/plain; charset=utf-8",
+            keys.collect::<Vec<_>>().join("\n"),
+        ),
+        _ => Response::ok(
+            "text/plain; charset=utf-8",
+            keys.collect::<Vec<_>>().join(","),
+        ),
+    }
+}
+/// Writes `response`, or 304 Not Modified if its ETag is `if_none_match`.
+fn write_response(
+    mut stream: &TcpStream,
+    response: &Response,
+    is_head: bool,
+    if_none_match: Option<&str>,
+) -> std::io::Result<()> {
+    let etag = format!(r#""{}""#, lock::hash(&response.body));
+    let (status, reason, body) = if response.status == 200 && if_none_match == Some(&etag) {
+        (304, "Not Modified", "")
+    } else {
+        (response.status, response.reason, response.body.as_str())
+    };
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status, reason);
+    if status != 304 {
+        head += format!("Content-Type: {}\r\n", response.content_type).as_str();
+        head += format!("Content-Length: {}\r\n", body.len()).as_str();
+    }
+    if response.status == 200 {
+        head += format!("ETag: {}\r\n", etag).as_str();
+    }
+    head += "Connection: close\r\n\r\n";
+    stream.write_all(head.as_bytes())?;
+    if !is_head {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+/// Decodes the `%XX` escapes in a URL's path.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| text.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Starts a server with one template. Returns its address.
+    fn start() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let templates = vec![TemplateInfo {
+            key: "rust".to_string(),
+            name: "Rust".to_string(),
+            contents: "\n### Rust ###\ntarget/\n".to_string(),
+            source: "test".to_string(),
+            cached: false,
+        }];
+        std::thread::spawn(move || serve(&listener, templates));
+        address
+    }
+    /// Sends `request` to the server at `address` and returns the response.
+    fn send(address: &str, request: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::default();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn host_is_written_into_the_file() {
+        let address = start();
+        let response = send(
+            &address,
+            "GET /api/rust HTTP/1.1\r\nHost: example.com:8080\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("# Created by http://example.com:8080/api/rust\n"));
+    }
+
+    #[test]
+    fn invalid_host_is_rejected() {
+        let address = start();
+        let response = send(
+            &address,
+            "GET /api/rust HTTP/1.1\r\nHost: example.com/\n# Injected\r\n\r\n",
+        );
+        assert!(
+            response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+            "{}",
+            response
+        );
+        assert!(!response.contains("Injected"));
+    }
+
+    #[test]
+    fn long_request_line_is_rejected() {
+        let address = start();
+        let target = "a".repeat(MAX_LINE_LENGTH as usize);
+        let response = send(&address, &format!("GET /api/{} HTTP/1.1\r\n\r\n", target));
+        assert!(
+            response.starts_with("HTTP/1.1 414 URI Too Long\r\n"),
+            "{}",
+            response
+        );
+    }
+
+    #[test]
+    fn long_header_is_rejected() {
+        let address = start();
+        let value = "a".repeat(MAX_LINE_LENGTH as usize);
+        let response = send(
+            &address,
+            &format!("GET /api/rust HTTP/1.1\r\nX-Long: {}\r\n\r\n", value),
+        );
+        assert!(
+            response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"),
+            "{}",
+            response
+        );
+    }
+
+    #[test]
+    fn too_many_headers_are_rejected() {
+        let address = start();
+        let headers = "X-Header: a\r\n".repeat(MAX_HEADERS + 1);
+        let response = send(
+            &address,
+            &format!("GET /api/rust HTTP/1.1\r\n{}\r\n", headers),
+        );
+        assert!(
+            response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"),
+            "{}",
+            response
+        );
+    }
+
+    #[test]
+    fn connections_over_the_limit_wait() {
+        let address = start();
+        // Connections that haven't sent their request yet
+        let mut idle: Vec<TcpStream> = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(&address).unwrap())
+            .collect();
+        let mut stream = TcpStream::connect(&address).unwrap();
+        stream.write_all(b"GET /api/rust HTTP/1.1\r\n\r\n").unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(500)))
+            .unwrap();
+        let mut response = String::default();
+        assert!(stream.read_to_string(&mut response).is_err());
+        // The request is answered when another connection closes
+        idle.pop();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    }
+}
@@ -25,34 +25,37 @@ pub struct Templates {
 }
 
 impl Templates {
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         Self { options: vec![] }
     }
-    pub(crate) fn set_list(&mut self, names: Vec<String>) {
+    pub fn set_list(&mut self, names: Vec<String>) {
         self.options.clear();
         for name in names {
             self.options.push(Template::new(name));
         }
     }
-    pub(crate) fn select_template(&mut self, name: &str) {
+    pub fn select_template(&mut self, name: &str) {
         self.set_template_selected_flag(name, true);
     }
-    pub(crate) fn unselect_template(&mut self, name: &str) {
+    pub fn select_templates(&mut self, names: &[String]) {
+        names.iter().for_each(|name| self.select_template(name));
+    }
+    pub fn unselect_template(&mut self, name: &str) {
         self.set_template_selected_flag(name, false);
     }
-    pub(crate) fn selected_templates(&self) -> Vec<Template> {
+    pub fn selected_templates(&self) -> Vec<Template> {
         self.get_list(true)
     }
-    pub(crate) fn unselected_templates(&self) -> Vec<Template> {
+    pub fn unselected_templates(&self) -> Vec<Template> {
         self.get_list(false)
     }
-    pub(crate) fn selected_template_names(&self) -> Vec<String> {
+    pub fn selected_template_names(&self) -> Vec<String> {
         self.selected_templates()
             .iter()
             .map(|template| template.name().to_string())
             .collect()
     }
-    pub(crate) fn any_selected(&self) -> bool {
+    pub fn any_selected(&self) -> bool {
         self.options.iter().any(|t| t.is_selected())
     }
 }
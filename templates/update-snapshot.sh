@@ -0,0 +1,17 @@
+#!/bin/sh
+# Saves a snapshot of all of the API's templates in templates/list.json, for the
+# builtin-templates feature to build into the program.
+set -eu
+
+api_url="${1:-https://www.toptal.com/developers/gitignore/api}"
+snapshot="$(dirname "$0")/list.json"
+
+curl --fail --silent --show-error --location -o "$snapshot.tmp" "$api_url/list?format=json"
+# The API answers some errors with an HTML page and a 200 status
+if [ "$(head -c 1 "$snapshot.tmp")" != "{" ]; then
+    rm -f "$snapshot.tmp"
+    echo "$api_url didn't answer with a JSON list of templates" >&2
+    exit 1
+fi
+mv "$snapshot.tmp" "$snapshot"
+echo "Saved $snapshot"
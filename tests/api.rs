@@ -0,0 +1,363 @@
+//! Runs gig-gen against a stand-in for the template API.
+//!
+//! The tests of what happens when the API can't be used are left out with the
+//! `builtin-templates` feature, which uses the built-in templates then.
+
+mod common;
+
+use common::{closed_port, stderr, stdout, MockServer, Response, TestDir};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Starts a mock of the gitignore.io API that answers with 503 Service Unavailable once the
+/// returned flag is set to false, and a directory that caches the templates it fetches from it.
+fn api_that_goes_down() -> (MockServer, Arc<AtomicBool>, TestDir) {
+    let up = Arc::new(AtomicBool::new(true));
+    let server_up = Arc::clone(&up);
+    let server = MockServer::start(move |request| {
+        if server_up.load(Ordering::Relaxed) {
+            common::gitignore_io(request)
+        } else {
+            Response::status(503)
+        }
+    });
+    let dir = TestDir::new(&format!(
+        "api_url = \"{}\"\n[network]\nretries = 0\n",
+        server.api_url()
+    ));
+    (server, up, dir)
+}
+
+#[test]
+fn list_prints_template_names() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::with_api(&server.api_url());
+    let output = dir.run(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "rust\nvim\n");
+    assert_eq!(server.requests()[0].path, "/api/list");
+}
+
+#[test]
+fn list_filters_template_names() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::with_api(&server.api_url());
+    let output = dir.run(&["list", "us"]);
+    assert_eq!(stdout(&output), "rust\n");
+}
+
+#[test]
+fn list_details_as_json() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::with_api(&server.api_url());
+    let output = dir.run(&["list", "--format", "json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let entries: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(entries[0]["name"], "rust");
+    assert_eq!(entries[0]["display_name"], "Rust");
+    assert_eq!(entries[0]["source"], server.api_url());
+    assert_eq!(entries[1]["name"], "vim");
+}
+
+#[test]
+fn generate_prints_templates() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::with_api(&server.api_url());
+    let output = dir.run(&["generate", "rust", "vim", "--output", "-"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = stdout(&output);
+    assert!(
+        text.starts_with("# Created by https://www.toptal.com/developers/gitignore/api/rust,vim\n")
+    );
+    assert!(text.contains("### Rust ###\n# Generated by Cargo\ndebug/\ntarget/\n"));
+    assert!(text.contains("### Vim ###\n*.swp\n"));
+    assert!(text.contains("# End of https://www.toptal.com/developers/gitignore/api/rust,vim\n"));
+    assert_eq!(server.requests()[0].path, "/api/rust,vim");
+}
+
+#[test]
+fn generate_writes_file_and_lockfile() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::with_api(&server.api_url());
+    let output = dir.run(&["generate", "rust", "--output", ".gitignore"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(dir.read(".gitignore").unwrap().contains("target/\n"));
+    let lockfile = dir.read(".gig-gen.lock").unwrap();
+    assert!(lockfile.contains(r#"name = "rust""#));
+    assert!(lockfile.contains(r#"hash = "sha256:"#));
+}
+
+#[test]
+fn generate_keeps_rules_added_to_the_configured_file() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::new(&format!(
+        "api_url = \"{}\"\noutput = \".gitignore\"\n[cache]\nenabled = false\n",
+        server.api_url()
+    ));
+    std::fs::write(dir.work_dir().join(".gitignore"), "# Local\n.env\n").unwrap();
+    let output = dir.run(&["generate", "rust"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let gitignore = dir.read(".gitignore").unwrap();
+    assert!(gitignore.starts_with("# Local\n.env\n"));
+    assert!(gitignore.contains("target/\n"));
+    // Generating again replaces the generated rules
+    let output = dir.run(&["generate", "vim"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let gitignore = dir.read(".gitignore").unwrap();
+    assert!(gitignore.starts_with("# Local\n.env\n"));
+    assert!(gitignore.contains("*.swp\n"));
+    assert!(!gitignore.contains("target/\n"));
+}
+
+#[test]
+fn configured_output_is_relative_to_the_repository_configuration() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::with_api(&server.api_url());
+    std::fs::create_dir(dir.work_dir().join(".git")).unwrap();
+    std::fs::write(
+        dir.work_dir().join(".gig-gen.toml"),
+        "output = \".gitignore\"\n",
+    )
+    .unwrap();
+    let sub_dir = dir.work_dir().join("src");
+    std::fs::create_dir(&sub_dir).unwrap();
+    let output = dir.run_in(&sub_dir, &["generate", "rust"], &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(dir.read(".gitignore").unwrap().contains("target/\n"));
+    assert!(!sub_dir.join(".gitignore").exists());
+}
+
+#[test]
+fn unknown_template_fails() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::with_api(&server.api_url());
+    let output = dir.run(&["generate", "rust", "nope", "--output", ".gitignore"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(stderr(&output).contains(r#"There is no template named "nope""#));
+    assert_eq!(dir.read(".gitignore"), None);
+}
+
+#[test]
+#[cfg(not(feature = "builtin-templates"))]
+fn error_status_fails() {
+    let server = MockServer::start(|_| Response::status(500).with_body("Oops"));
+    let dir = TestDir::with_api(&server.api_url());
+    let output = dir.run(&["generate", "rust", "--output", ".gitignore"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stderr(&output).contains("500"));
+    assert_eq!(dir.read(".gitignore"), None);
+}
+
+#[test]
+#[cfg(not(feature = "builtin-templates"))]
+fn server_error_is_tried_again() {
+    let server = MockServer::start(|request| {
+        if request.path == "/api/list" {
+            Response::text("rust,vim")
+        } else {
+            Response::status(503).with_header("Retry-After", "0")
+        }
+    });
+    let dir = TestDir::new(&format!(
+        "api_url = \"{}\"\n[cache]\nenabled = false\n[network]\nretries = 2\n",
+        server.api_url()
+    ));
+    let output = dir.run(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = dir.run(&["generate", "rust", "--output", "-"]);
+    assert_eq!(output.status.code(), Some(4));
+    // The list, then the first try and two more
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[test]
+fn rate_limited_request_succeeds_after_waiting() {
+    let server = {
+        let count = std::sync::atomic::AtomicUsize::new(0);
+        MockServer::start(move |request| {
+            if count.fetch_add(1, std::sync::atomic::Ordering::Relaxed) == 0 {
+                Response::status(429).with_header("Retry-After", "1")
+            } else {
+                common::gitignore_io(request)
+            }
+        })
+    };
+    let dir = TestDir::new(&format!(
+        "api_url = \"{}\"\n[cache]\nenabled = false\n[network]\nretries = 1\n",
+        server.api_url()
+    ));
+    let start = Instant::now();
+    let output = dir.run(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+#[cfg(not(feature = "builtin-templates"))]
+fn slow_response_times_out() {
+    let server =
+        MockServer::start(|_| Response::text("rust,vim").with_delay(Duration::from_secs(10)));
+    let dir = TestDir::new(&format!(
+        "api_url = \"{}\"\n[cache]\nenabled = false\n[network]\nretries = 0\ntimeout = 1\n",
+        server.api_url()
+    ));
+    let start = Instant::now();
+    let output = dir.run(&["list"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+#[cfg(not(feature = "builtin-templates"))]
+fn unreachable_api_fails() {
+    let dir = TestDir::with_api(&format!("http://{}/api", closed_port()));
+    let output = dir.run(&["list"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).contains("Problem getting templates"));
+}
+
+#[test]
+fn no_proxy_hosts_are_reached_directly() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::with_api(&server.api_url());
+    let proxy = format!("http://{}", closed_port());
+    let output = dir.run_with_env(
+        &["list"],
+        &[("http_proxy", &proxy), ("no_proxy", "127.0.0.1")],
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "rust\nvim\n");
+    // Without NO_PROXY, the request goes to the proxy, which can't be reached
+    if !cfg!(feature = "builtin-templates") {
+        let output = dir.run_with_env(&["list"], &[("http_proxy", &proxy)]);
+        assert_eq!(output.status.code(), Some(3));
+    }
+}
+
+#[test]
+fn ca_bundle_is_added_to_the_trusted_certificates() {
+    let server = MockServer::gitignore_io();
+    let ca_bundle = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ca.pem");
+    let dir = TestDir::new(&format!(
+        "api_url = \"{}\"\n[network]\nca_bundle = {:?}\n",
+        server.api_url(),
+        ca_bundle.display().to_string()
+    ));
+    let output = dir.run(&["list"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let certificate = std::fs::read_to_string(&ca_bundle).unwrap();
+    let trusted: Vec<_> = std::fs::read_dir(dir.cache_dir().join("gig-gen"))
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap_or_default())
+        .filter(|contents| contents.contains(&certificate))
+        .collect();
+    assert_eq!(trusted.len(), 1);
+}
+
+#[test]
+fn ca_bundle_without_certificates_fails() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::new(&format!(
+        "api_url = \"{}\"\n[network]\nca_bundle = \"ca.pem\"\n",
+        server.api_url()
+    ));
+    std::fs::write(dir.work_dir().join("ca.pem"), "not a certificate\n").unwrap();
+    let output = dir.run(&["list"]);
+    assert_eq!(output.status.code(), Some(7));
+    assert!(stderr(&output).contains("no PEM certificates"));
+}
+
+#[test]
+fn malformed_json_fails() {
+    let server = MockServer::start(|_| Response::json(r#"{"rust": ["#));
+    let dir = TestDir::with_api(&server.api_url());
+    let output = dir.run(&["list", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(7));
+    assert!(stderr(&output).contains("list?format=json"));
+}
+
+#[test]
+fn html_error_page_is_not_written() {
+    let server = MockServer::start(|_| {
+        Response::status(200)
+            .with_header("Content-Type", "text/html")
+            .with_body("<html><body>Sign in to continue</body></html>")
+    });
+    let dir = TestDir::with_api(&server.api_url());
+    let output = dir.run(&["generate", "rust", "--output", ".gitignore"]);
+    assert_eq!(output.status.code(), Some(7));
+    assert!(stderr(&output).contains("text/html"));
+    assert_eq!(dir.read(".gitignore"), None);
+}
+
+#[test]
+fn cached_templates_are_revalidated() {
+    let server = MockServer::start(|request| {
+        if request.header("if-none-match") == Some(r#""v1""#) {
+            Response::status(304)
+        } else {
+            common::gitignore_io(request).with_header("ETag", r#""v1""#)
+        }
+    });
+    let dir = TestDir::new(&format!(
+        "api_url = \"{}\"\n[network]\nretries = 0\n",
+        server.api_url()
+    ));
+    let output = dir.run(&["list", "--format", "csv"]);
+    assert!(
+        stdout(&output).contains("rust,Rust,"),
+        "{}",
+        stderr(&output)
+    );
+    // A fresh cached copy is used without asking
+    let output = dir.run(&["list", "--format", "csv"]);
+    assert!(stdout(&output).contains("rust,Rust,"));
+    assert_eq!(server.requests().len(), 1);
+    // With --refresh, the API says the cached copy is still current
+    let output = dir.run(&["--refresh", "list", "--format", "csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains(",true"));
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].header("if-none-match"), Some(r#""v1""#));
+}
+
+#[test]
+fn locked_generate_fails_when_the_templates_cant_be_checked() {
+    let (_server, up, dir) = api_that_goes_down();
+    let output = dir.run(&["generate", "rust", "--output", ".gitignore"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    up.store(false, Ordering::Relaxed);
+    // The cached templates are still fresh, but aren't used without checking them
+    let output = dir.run(&["generate", "--locked", "--output", "-"]);
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+}
+
+#[test]
+fn outdated_fails_when_the_templates_cant_be_checked() {
+    let (_server, up, dir) = api_that_goes_down();
+    let output = dir.run(&["generate", "rust", "--output", ".gitignore"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = dir.run(&["outdated"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "rust: up to date\n");
+    up.store(false, Ordering::Relaxed);
+    let output = dir.run(&["outdated"]);
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn upgrade_fails_when_the_templates_cant_be_checked() {
+    let (_server, up, dir) = api_that_goes_down();
+    let output = dir.run(&["generate", "rust", "--output", ".gitignore"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let gitignore = dir.read(".gitignore").unwrap();
+    up.store(false, Ordering::Relaxed);
+    let output = dir.run(&["upgrade", "--yes"]);
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert_eq!(dir.read(".gitignore").unwrap(), gitignore);
+}
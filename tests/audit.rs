@@ -0,0 +1,53 @@
+//! Runs `gig-gen audit` in a git repository.
+
+mod common;
+
+use common::{stderr, stdout, MockServer, TestDir};
+use std::path::Path;
+use std::process::Command;
+
+fn write(path: &Path, contents: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn paths_git_ignores_are_not_clutter() {
+    let server = MockServer::gitignore_io();
+    let dir = TestDir::with_api(&server.api_url());
+    let work_dir = dir.work_dir();
+    git(&work_dir, &["init", "--quiet"]);
+    write(&work_dir.join("src/main.rs"), "fn main() {}\n");
+    write(&work_dir.join("src/.gitignore"), "*.swp\n");
+    git(&work_dir, &["add", "src"]);
+    // Ignored by src/.gitignore, .git/info/exclude and the user's excludes file
+    write(&work_dir.join("src/main.rs.swp"), "");
+    write(&work_dir.join(".git/info/exclude"), "secret.txt\n");
+    write(&work_dir.join("secret.txt"), "");
+    write(&dir.config_dir().join("git/ignore"), "*.bak\n");
+    write(&work_dir.join("main.rs.bak"), "");
+    std::fs::create_dir(work_dir.join("empty")).unwrap();
+    // Clutter
+    write(&work_dir.join("notes.swp"), "");
+    write(&work_dir.join("target/debug/app"), "");
+
+    let output = dir.run(&["audit"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = stdout(&output);
+    assert!(
+        stdout.starts_with("Untracked files that aren't ignored:\n  notes.swp\n  target/\n\n"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("  rust (1): target/\n"));
+    assert!(stdout.contains("  vim (1): notes.swp\n"));
+}
@@ -0,0 +1,25 @@
+//! Runs gig-gen with the templates built in with the `builtin-templates` feature:
+//! `cargo test --features builtin-templates`.
+#![cfg(feature = "builtin-templates")]
+
+mod common;
+
+use common::{closed_port, stderr, stdout, TestDir};
+
+#[test]
+fn list_builtin_templates() {
+    let dir = TestDir::with_api(&format!("http://{}/api", closed_port()));
+    let output = dir.run(&["list", "--source", "builtin"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let names = stdout(&output);
+    assert!(names.lines().any(|name| name == "rust"), "{}", names);
+}
+
+#[test]
+fn builtin_templates_are_used_when_the_api_cant_be_reached() {
+    let dir = TestDir::with_api(&format!("http://{}/api", closed_port()));
+    let output = dir.run(&["generate", "rust", "--output", "-"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("### Rust ###\n"));
+    assert!(stdout(&output).contains("target/\n"));
+}
@@ -0,0 +1,286 @@
+//! A stand-in for the template API, and a way to run gig-gen against it.
+
+// Each test file uses a different part of this module
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The templates the mock API serves, in the `list?format=json` format.
+pub const TEMPLATES_JSON: &str = r#"{
+  "rust": {"key": "rust", "name": "Rust", "fileName": "Rust.gitignore",
+           "contents": "\n### Rust ###\n# Generated by Cargo\ndebug/\ntarget/\n"},
+  "vim": {"key": "vim", "name": "Vim", "fileName": "Vim.gitignore",
+          "contents": "\n### Vim ###\n*.swp\n*~\n"}
+}"#;
+
+/// A request the mock API received.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub path: String,
+    /// The headers, with lowercase names
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A response for the mock API to send.
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// How long to wait before answering
+    pub delay: Duration,
+}
+
+impl Response {
+    pub fn status(status: u16) -> Self {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: String::default(),
+            delay: Duration::ZERO,
+        }
+    }
+    pub fn text(body: &str) -> Self {
+        Response::status(200)
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+            .with_body(body)
+    }
+    pub fn json(body: &str) -> Self {
+        Response::status(200)
+            .with_header("Content-Type", "application/json; charset=utf-8")
+            .with_body(body)
+    }
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+    pub fn with_body(mut self, body: &str) -> Self {
+        self.body = body.to_string();
+        self
+    }
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+type Handler = dyn Fn(&Request) -> Response + Send + Sync;
+
+/// An HTTP server on a free local port that answers each request with a handler's response,
+/// and records the requests. It stops when the test process ends.
+pub struct MockServer {
+    address: SocketAddr,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    pub fn start(handler: impl Fn(&Request) -> Response + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Can't start the mock server");
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+        let server_requests = Arc::clone(&requests);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let handler = Arc::clone(&handler);
+                let requests = Arc::clone(&server_requests);
+                std::thread::spawn(move || {
+                    let _ = answer(stream, handler.as_ref(), &requests);
+                });
+            }
+        });
+        MockServer { address, requests }
+    }
+    /// A mock of the gitignore.io API that serves `TEMPLATES_JSON`.
+    pub fn gitignore_io() -> Self {
+        MockServer::start(gitignore_io)
+    }
+    /// The API URL to configure gig-gen with.
+    pub fn api_url(&self) -> String {
+        format!("http://{}/api", self.address)
+    }
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn answer(
+    stream: TcpStream,
+    handler: &Handler,
+    requests: &Mutex<Vec<Request>>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::default();
+    reader.read_line(&mut request_line)?;
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::default();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+    let request = Request { path, headers };
+    requests.lock().unwrap().push(request.clone());
+    let response = handler(&request);
+    std::thread::sleep(response.delay);
+    let mut head = format!("HTTP/1.1 {} Mock\r\n", response.status);
+    for (name, value) in &response.headers {
+        head += format!("{}: {}\r\n", name, value).as_str();
+    }
+    head += format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    )
+    .as_str();
+    let mut stream = &stream;
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+/// Answers a request the way the gitignore.io API does, with the templates in `TEMPLATES_JSON`.
+pub fn gitignore_io(request: &Request) -> Response {
+    let templates: serde_json::Value = serde_json::from_str(TEMPLATES_JSON).unwrap();
+    let templates = templates.as_object().unwrap();
+    let Some(names) = request.path.strip_prefix("/api/") else {
+        return Response::status(404);
+    };
+    match names {
+        "list" => Response::text(&templates.keys().cloned().collect::<Vec<_>>().join(",")),
+        "list?format=json" => Response::json(TEMPLATES_JSON),
+        names => {
+            let url = format!("https://www.toptal.com/developers/gitignore/api/{}", names);
+            let mut body = format!(
+                "# Created by {}\n# Edit at https://www.toptal.com/developers/gitignore?templates={}\n",
+                url, names
+            );
+            for name in names.split(',') {
+                match templates.get(name) {
+                    Some(template) => body += template["contents"].as_str().unwrap(),
+                    None => {
+                        return Response::status(404).with_body(&format!(
+                            "\n#!! ERROR: {} is undefined. Use list command to see defined gitignore types !!#\n",
+                            name
+                        ))
+                    }
+                }
+            }
+            body += format!("\n# End of {}\n", url).as_str();
+            Response::text(&body)
+        }
+    }
+}
+
+/// A temporary directory to run gig-gen in, with its own configuration and cache.
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// Creates a directory with a configuration file that has `config` in it.
+    pub fn new(config: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "gig-gen-test-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(path.join("config/gig-gen")).unwrap();
+        std::fs::create_dir_all(path.join("work")).unwrap();
+        std::fs::write(path.join("config/gig-gen/config.toml"), config).unwrap();
+        TestDir { path }
+    }
+    /// Creates a directory with a configuration that uses `api_url`, doesn't cache
+    /// the templates, and doesn't try requests again.
+    pub fn with_api(api_url: &str) -> Self {
+        TestDir::new(&format!(
+            "api_url = \"{}\"\n[cache]\nenabled = false\n[network]\nretries = 0\ntimeout = 5\n",
+            api_url
+        ))
+    }
+    /// The directory gig-gen runs in.
+    pub fn work_dir(&self) -> PathBuf {
+        self.path.join("work")
+    }
+    /// The directory `XDG_CONFIG_HOME` is set to.
+    pub fn config_dir(&self) -> PathBuf {
+        self.path.join("config")
+    }
+    pub fn cache_dir(&self) -> PathBuf {
+        self.path.join("cache")
+    }
+    pub fn read(&self, name: &str) -> Option<String> {
+        std::fs::read_to_string(self.work_dir().join(name)).ok()
+    }
+    /// Runs gig-gen with `args`, without any of the user's settings or proxies.
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.run_with_env(args, &[])
+    }
+    /// Runs gig-gen with `args` and the environment variables in `env`, without any of
+    /// the user's settings or proxies.
+    pub fn run_with_env(&self, args: &[&str], env: &[(&str, &str)]) -> Output {
+        self.run_in(&self.work_dir(), args, env)
+    }
+    /// Runs gig-gen with `args` and the environment variables in `env` in `dir`, without
+    /// any of the user's settings or proxies.
+    pub fn run_in(&self, dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_gig-gen"))
+            .args(args)
+            .current_dir(dir)
+            .env("XDG_CONFIG_HOME", self.config_dir())
+            .env("XDG_CACHE_HOME", self.cache_dir())
+            .env("HOME", &self.path)
+            .env_remove("https_proxy")
+            .env_remove("HTTPS_PROXY")
+            .env_remove("http_proxy")
+            .env_remove("all_proxy")
+            .env_remove("ALL_PROXY")
+            .envs(env.iter().copied())
+            .output()
+            .expect("Can't run gig-gen")
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+/// Returns the address of a local port that nothing is listening on.
+pub fn closed_port() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
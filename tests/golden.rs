@@ -0,0 +1,213 @@
+//! Compares the files gig-gen generates from the templates in `tests/fixtures/templates`
+//! with the expected files in `tests/fixtures/golden`.
+//!
+//! After changing what gig-gen generates, run the tests with `GIG_GEN_BLESS=1` to write
+//! the new output as the expected files, and check the changes to them before committing.
+
+mod common;
+
+use common::{stderr, stdout, TestDir};
+use gig_gen::internal::{diff, server};
+use gig_gen::{Format, SaveMode};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+/// The environment variable that makes the tests write the expected files.
+const BLESS: &str = "GIG_GEN_BLESS";
+/// The API URL the expected files have in place of the test server's.
+const API_URL: &str = "https://www.toptal.com/developers/gitignore/api";
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+/// Serves the fixture templates on a free local port. Returns the API URL.
+fn start_api() -> String {
+    let templates = server::read_templates(&fixtures_dir().join("templates")).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Can't start the template server");
+    let api_url = format!("http://{}/api", listener.local_addr().unwrap());
+    std::thread::spawn(move || server::serve(&listener, templates));
+    api_url
+}
+/// Fails if `actual` isn't the same as the expected file `name`, or writes it as the
+/// expected file if `GIG_GEN_BLESS` is set.
+fn check(name: &str, actual: &str) {
+    let path = fixtures_dir().join("golden").join(name);
+    if std::env::var_os(BLESS).is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "There is no {}. Run the tests with {}=1 to write it",
+            path.display(),
+            BLESS
+        )
+    });
+    let changes = diff::unified(
+        &expected.lines().collect::<Vec<_>>(),
+        &actual.lines().collect::<Vec<_>>(),
+        name,
+        "generated",
+    );
+    assert!(
+        expected == actual,
+        "The output doesn't match {}. If it should, run the tests with {}=1\n{}",
+        path.display(),
+        BLESS,
+        changes
+    );
+}
+/// Checks what `gig-gen generate <args>` writes to stdout against the expected file `name`.
+fn check_generate(name: &str, args: &[&str]) {
+    let api_url = start_api();
+    let dir = TestDir::with_api(&api_url);
+    let output = dir.run(&[&["generate", "--output", "-"], args].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+    check(name, &stdout(&output).replace(&api_url, API_URL));
+}
+/// Saves the file gig-gen generates with `args` in `format` to a file that contains
+/// `existing`, the way the interactive app does, and compares the result with the
+/// expected file `name`.
+fn check_saved(name: &str, existing: &str, args: &[&str], format: Format, mode: SaveMode) {
+    let api_url = start_api();
+    let dir = TestDir::with_api(&api_url);
+    let output = dir.run(&[&["generate", "--output", "-"], args].concat());
+    assert!(output.status.success(), "{}", stderr(&output));
+    let generated = stdout(&output);
+    // println! adds a line break to the end of the file
+    let generated = generated.strip_suffix('\n').unwrap_or(&generated);
+    let path = dir.work_dir().join(format.file_name());
+    std::fs::write(&path, existing.replace(API_URL, &api_url)).unwrap();
+    let saved = gig_gen::saved_contents(&path, generated, format, mode).unwrap();
+    check(name, &saved.replace(&api_url, API_URL));
+}
+
+#[test]
+fn one_template() {
+    check_generate("rust.gitignore", &["rust"]);
+}
+
+#[test]
+fn several_templates() {
+    check_generate("rust-vim-macos.gitignore", &["rust", "vim", "macos"]);
+}
+
+#[test]
+fn repeated_rules_are_left_out() {
+    // Vim and Linux both ignore *~
+    check_generate("vim-linux.gitignore", &["vim", "linux"]);
+}
+
+#[test]
+fn rules_from_each_template() {
+    check_generate(
+        "vim-linux.json",
+        &["vim", "linux", "--output-format", "json"],
+    );
+}
+
+#[test]
+fn dockerignore() {
+    check_generate(
+        "node-macos.dockerignore",
+        &["node", "macos", "--format", "dockerignore"],
+    );
+}
+
+#[test]
+fn npmignore() {
+    check_generate("node.npmignore", &["node", "--format", "npmignore"]);
+}
+
+#[test]
+fn prettierignore() {
+    check_generate(
+        "node-rust.prettierignore",
+        &["node", "rust", "--format", "prettierignore"],
+    );
+}
+
+#[test]
+fn eslintignore() {
+    check_generate("node.eslintignore", &["node", "--format", "eslintignore"]);
+}
+
+#[test]
+fn hgignore() {
+    check_generate(
+        "rust-vim.hgignore",
+        &["rust", "vim", "--format", "hgignore"],
+    );
+}
+
+#[test]
+fn append_to_dockerignore() {
+    // The Vim rule that's already in the file is left out
+    check_saved(
+        "appended.dockerignore",
+        "# Local\n.env\n**/[._]*.s[a-v][a-z]\n",
+        &["vim", "--format", "dockerignore"],
+        Format::Dockerignore,
+        SaveMode::Append,
+    );
+}
+
+#[test]
+fn update_dockerignore() {
+    let existing = format!(
+        "# Local\n.env\n\n# Created by {0}/rust\n\n### Rust ###\n**/target\n\n# End of {0}/rust\n\n# More local rules\n**/*.local\n",
+        API_URL
+    );
+    check_saved(
+        "updated.dockerignore",
+        &existing,
+        &["rust", "vim", "--format", "dockerignore"],
+        Format::Dockerignore,
+        SaveMode::Update,
+    );
+}
+
+#[test]
+fn append_to_hgignore() {
+    // Every rule is kept, because a line repeated after a different syntax line is a
+    // different rule
+    check_saved(
+        "appended.hgignore",
+        "# Local\nsyntax: glob\n*.swp\nsyntax: regexp\n^build(?:/|$)\n",
+        &["vim", "--format", "hgignore"],
+        Format::Hgignore,
+        SaveMode::Append,
+    );
+}
+
+#[test]
+fn update_hgignore() {
+    let existing = format!(
+        "# Local\nsyntax: glob\n.env\n\n# Created by {0}/rust\n\n### Rust ###\nsyntax: glob\ntarget\n\n# End of {0}/rust\n\n# More local rules\nsyntax: regexp\n^build(?:/|$)\n",
+        API_URL
+    );
+    check_saved(
+        "updated.hgignore",
+        &existing,
+        &["rust", "vim", "--format", "hgignore"],
+        Format::Hgignore,
+        SaveMode::Update,
+    );
+}
+
+#[test]
+fn upgrade_replaces_the_generated_part() {
+    let api_url = start_api();
+    let dir = TestDir::with_api(&api_url);
+    // An older version of the templates, between rules that were added by hand
+    let gitignore = format!(
+        "# Local\n.env\n.DS_Store\n\n# Created by {0}/macos,rust\n\n### macOS ###\n.DS_Store\n\n### Rust ###\ntarget/\n\n# End of {0}/macos,rust\n\n# More local rules\n*.local\n",
+        api_url
+    );
+    std::fs::write(dir.work_dir().join(".gitignore"), gitignore).unwrap();
+    let output = dir.run(&["upgrade", "--yes"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).ends_with("Upgraded .gitignore.\n"));
+    let upgraded = dir.read(".gitignore").unwrap();
+    check("upgraded.gitignore", &upgraded.replace(&api_url, API_URL));
+}
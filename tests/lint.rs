@@ -0,0 +1,23 @@
+//! Runs `gig-gen lint` on files with and without problems.
+
+mod common;
+
+use common::{stderr, stdout, TestDir};
+
+#[test]
+fn problems_exit_with_status_1() {
+    let dir = TestDir::new("");
+    std::fs::write(dir.work_dir().join(".gitignore"), "*.log\n*.log\n").unwrap();
+    let output = dir.run(&["lint"]);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert!(stdout(&output).starts_with(".gitignore:2: duplicate:"));
+}
+
+#[test]
+fn fixed_problems_exit_with_status_0() {
+    let dir = TestDir::new("");
+    std::fs::write(dir.work_dir().join(".gitignore"), "*.log\n*.log\n").unwrap();
+    let output = dir.run(&["lint", "--fix"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(dir.read(".gitignore").unwrap(), "*.log\n");
+}